@@ -0,0 +1,323 @@
+// Goals reached during runs, remembered between sessions, each one unlocking
+// a skin or a rope color for the first player
+
+use bevy::log::warn;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::GameAssets;
+use crate::sim::GameEvent;
+use crate::storage;
+use crate::style::StyleScore;
+use crate::{GameMode, Line, Player, PlayerId};
+
+const SAVE_KEY: &str = "achievements";
+// seconds a toast stays up, it fades out over the last one
+const TOAST_TIME: f32 = 4.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Achievement {
+    Reach5000,
+    Hooks50,
+    NoWalls1000,
+    Combo10,
+    NearMiss,
+}
+
+impl Achievement {
+    pub(crate) const ALL: [Achievement; 5] = [
+        Achievement::Reach5000,
+        Achievement::Hooks50,
+        Achievement::NoWalls1000,
+        Achievement::Combo10,
+        Achievement::NearMiss,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Achievement::Reach5000 => "High flyer",
+            Achievement::Hooks50 => "Busy hands",
+            Achievement::NoWalls1000 => "Clean climb",
+            Achievement::Combo10 => "Chain gang",
+            Achievement::NearMiss => "Close call",
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Achievement::Reach5000 => "Reach 5000",
+            Achievement::Hooks50 => "Hook 50 mines in one run",
+            Achievement::NoWalls1000 => "Climb 1000 without touching a wall",
+            Achievement::Combo10 => "Hook 10 mines in a row",
+            Achievement::NearMiss => "Escape the bottom of the screen",
+        }
+    }
+}
+
+/// A look for the first player, None is there from the start
+pub(crate) struct Cosmetic {
+    pub(crate) name: &'static str,
+    pub(crate) color: Color,
+    pub(crate) unlock: Option<Achievement>,
+}
+
+pub(crate) const SKINS: [Cosmetic; 4] = [
+    Cosmetic {
+        name: "Classic",
+        color: Color::WHITE,
+        unlock: None,
+    },
+    Cosmetic {
+        name: "Gold",
+        color: Color::rgb(1.0, 0.85, 0.3),
+        unlock: Some(Achievement::Reach5000),
+    },
+    Cosmetic {
+        name: "Mint",
+        color: Color::rgb(0.5, 1.0, 0.7),
+        unlock: Some(Achievement::Hooks50),
+    },
+    Cosmetic {
+        name: "Shadow",
+        color: Color::rgb(0.45, 0.4, 0.6),
+        unlock: Some(Achievement::NearMiss),
+    },
+];
+
+pub(crate) const ROPES: [Cosmetic; 3] = [
+    Cosmetic {
+        name: "Classic",
+        color: Color::WHITE,
+        unlock: None,
+    },
+    Cosmetic {
+        name: "Red",
+        color: Color::rgb(1.0, 0.3, 0.3),
+        unlock: Some(Achievement::NoWalls1000),
+    },
+    Cosmetic {
+        name: "Blue",
+        color: Color::rgb(0.3, 0.6, 1.0),
+        unlock: Some(Achievement::Combo10),
+    },
+];
+
+/// What was unlocked so far and which of the unlocked looks are picked
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Achievements {
+    pub(crate) unlocked: Vec<Achievement>,
+    /// Index into SKINS
+    pub(crate) skin: usize,
+    /// Index into ROPES
+    pub(crate) rope: usize,
+}
+
+impl Achievements {
+    pub(crate) fn load() -> Self {
+        storage::load(SAVE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self) {
+        let json = serde_json::to_string(self).expect("Achievements are always valid JSON");
+        if let Err(e) = storage::save(SAVE_KEY, &json) {
+            warn!("Could not save achievements: {}", e);
+        }
+    }
+
+    pub(crate) fn has(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    fn available(&self, cosmetic: &Cosmetic) -> bool {
+        cosmetic.unlock.map_or(true, |a| self.has(a))
+    }
+
+    /// The picked one, or the first if a save points at a locked one
+    fn pick<'a>(&self, cosmetics: &'a [Cosmetic], index: usize) -> &'a Cosmetic {
+        match cosmetics.get(index) {
+            Some(cosmetic) if self.available(cosmetic) => cosmetic,
+            _ => &cosmetics[0],
+        }
+    }
+
+    /// The next unlocked one after `index`, wrapping around
+    fn next(&self, cosmetics: &[Cosmetic], index: usize) -> usize {
+        (1..=cosmetics.len())
+            .map(|step| (index + step) % cosmetics.len())
+            .find(|i| self.available(&cosmetics[*i]))
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn next_skin(&mut self) {
+        self.skin = self.next(&SKINS, self.skin);
+    }
+
+    pub(crate) fn next_rope(&mut self) {
+        self.rope = self.next(&ROPES, self.rope);
+    }
+
+    pub(crate) fn skin(&self) -> &Cosmetic {
+        self.pick(&SKINS, self.skin)
+    }
+
+    pub(crate) fn rope(&self) -> &Cosmetic {
+        self.pick(&ROPES, self.rope)
+    }
+
+    /// What the menu lists for an achievement
+    pub(crate) fn label(&self, achievement: Achievement) -> String {
+        let mark = if self.has(achievement) { "x" } else { " " };
+        format!(
+            "[{}] {}: {}",
+            mark,
+            achievement.name(),
+            achievement.description()
+        )
+    }
+}
+
+/// How far the first player got towards the achievements in this run
+#[derive(Debug, Default)]
+pub(crate) struct AchievementRun {
+    // height of the last wall touch, or where the run started
+    wall_height: Option<f32>,
+}
+
+struct Toast(Timer);
+
+pub(crate) fn reset_achievement_run(mut run: ResMut<AchievementRun>) {
+    *run = AchievementRun::default();
+}
+
+/// Check the achievements once per tick, after everything moved
+pub(crate) fn achievement_system(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mode: Res<GameMode>,
+    style: Res<StyleScore>,
+    mut achievements: ResMut<Achievements>,
+    mut run: ResMut<AchievementRun>,
+    mut events: EventReader<GameEvent>,
+    q_player: Query<(&Player, &PlayerId)>,
+    q_toast: Query<&Toast>,
+) {
+    // play-tests start wherever the editor says, so they don't count
+    if *mode == GameMode::PlayTest {
+        return;
+    }
+    let player = match q_player.iter().find(|(_, id)| **id == PlayerId(0)) {
+        Some((player, _)) => player,
+        None => return,
+    };
+    let height = player.maxheight;
+    // this tick's events first, a wall touch now counts against this tick
+    for event in events.iter() {
+        if let GameEvent::WallBounce { player: 0, .. } = event {
+            run.wall_height = Some(height);
+        }
+    }
+    let wall_height = *run.wall_height.get_or_insert(height);
+
+    let mut reached = Vec::new();
+    if height >= 5000. {
+        reached.push(Achievement::Reach5000);
+    }
+    // same as the style score, grabbing one mine over and over is one hook
+    if style.hooks >= 50 {
+        reached.push(Achievement::Hooks50);
+    }
+    if !player.dead() && height - wall_height >= 1000. {
+        reached.push(Achievement::NoWalls1000);
+    }
+    if style.best_combo >= 10 {
+        reached.push(Achievement::Combo10);
+    }
+    if style.near_misses > 0 {
+        reached.push(Achievement::NearMiss);
+    }
+
+    let mut toasts = q_toast.iter().count();
+    for achievement in reached {
+        // only touch the resource for news, the menu redraws when it changes
+        if !achievements.has(achievement) {
+            achievements.unlocked.push(achievement);
+            achievements.save();
+            spawn_toast(&mut commands, &assets, achievement, toasts);
+            toasts += 1;
+        }
+    }
+}
+
+fn spawn_toast(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    achievement: Achievement,
+    stacked: usize,
+) {
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text::with_section(
+                format!("Achievement: {}", achievement.name()),
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 28.0,
+                    color: Color::rgb(1.0, 0.8, 0.1),
+                },
+                Default::default(),
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(20.0 + 36.0 * stacked as f32),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Toast(Timer::from_seconds(TOAST_TIME, false)));
+}
+
+/// Fade toasts out and remove them, on every screen
+pub(crate) fn toast_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_toast: Query<(Entity, &mut Toast, &mut Text)>,
+) {
+    for (entity, mut toast, mut text) in q_toast.iter_mut() {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let left = toast.0.duration().as_secs_f32() - toast.0.elapsed_secs();
+        text.sections[0].style.color.set_a(left.min(1.));
+    }
+}
+
+/// Dress up the first player and its rope for the run
+pub(crate) fn apply_cosmetics(
+    achievements: Res<Achievements>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut q_player: Query<(Entity, &PlayerId, &mut TextureAtlasSprite), With<Player>>,
+    q_line: Query<(&Line, &Handle<ColorMaterial>)>,
+) {
+    for (entity, id, mut sprite) in q_player.iter_mut() {
+        if *id != PlayerId(0) {
+            continue;
+        }
+        sprite.color = achievements.skin().color;
+        for (line, material) in q_line.iter() {
+            if line.0 == entity {
+                if let Some(material) = materials.get_mut(material) {
+                    material.color = achievements.rope().color;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// Picks the player's animation from how it moves, and turns it towards the
+// mine on the other end of its rope
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::sim::GameEvent;
+use crate::{Mine, Player, PlayerId};
+
+// frames per state, player_sheet.png has one row of them per state
+pub(crate) const FRAMES: usize = 4;
+const FRAME_TIME: f32 = 0.12;
+// a wall hit plays its frames once
+const HIT_WALL_TIME: f32 = FRAMES as f32 * FRAME_TIME;
+// slower than this counts as idle, in units per tick
+const IDLE_SPEED: f32 = 2.0;
+// how quickly the sprite turns to the rope, higher is snappier
+const TURN_RATE: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AnimState {
+    Idle,
+    Rising,
+    Falling,
+    Swinging,
+    HitWall,
+    Dead,
+}
+
+impl AnimState {
+    /// In the order of the rows of player_sheet.png
+    pub const ALL: [AnimState; 6] = [
+        AnimState::Idle,
+        AnimState::Rising,
+        AnimState::Falling,
+        AnimState::Swinging,
+        AnimState::HitWall,
+        AnimState::Dead,
+    ];
+
+    fn row(self) -> usize {
+        Self::ALL.iter().position(|s| *s == self).unwrap_or(0)
+    }
+
+    /// Stays on its last frame instead of looping
+    fn once(self) -> bool {
+        self == AnimState::HitWall
+    }
+}
+
+/// Dying beats everything, then a fresh wall hit, then the rope, then how it moves
+fn anim_state(velocity: Vec3, hooked: bool, dead: bool, hit_wall: bool) -> AnimState {
+    if dead {
+        AnimState::Dead
+    } else if hit_wall {
+        AnimState::HitWall
+    } else if hooked {
+        AnimState::Swinging
+    } else if velocity.y > IDLE_SPEED {
+        AnimState::Rising
+    } else if velocity.y < -IDLE_SPEED {
+        AnimState::Falling
+    } else {
+        AnimState::Idle
+    }
+}
+
+pub(crate) struct Animation {
+    state: AnimState,
+    frame: usize,
+    timer: Timer,
+    // seconds the wall hit keeps showing
+    hit_wall: f32,
+    // radians, counterclockwise from upright
+    angle: f32,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            state: AnimState::Idle,
+            frame: 0,
+            timer: Timer::from_seconds(FRAME_TIME, true),
+            hit_wall: 0.,
+            angle: 0.,
+        }
+    }
+}
+
+/// Step the players' animations and point their tops along their ropes
+pub(crate) fn animation_system(
+    time: Res<Time>,
+    mut events: EventReader<GameEvent>,
+    q_mine: Query<(&Mine, &Transform), Without<Player>>,
+    mut q_player: Query<(
+        &Player,
+        &PlayerId,
+        &mut Animation,
+        &mut TextureAtlasSprite,
+        &mut Transform,
+    )>,
+) {
+    let bounced: Vec<u8> = events
+        .iter()
+        .filter_map(|event| match event {
+            GameEvent::WallBounce { player, .. } => Some(*player),
+            _ => None,
+        })
+        .collect();
+    let dt = time.delta_seconds();
+    for (player, id, mut anim, mut sprite, mut transform) in q_player.iter_mut() {
+        anim.hit_wall = if bounced.contains(&id.0) {
+            HIT_WALL_TIME
+        } else {
+            (anim.hit_wall - dt).max(0.)
+        };
+        let state = anim_state(
+            player.velocity,
+            player.hooked.is_some(),
+            player.dead(),
+            anim.hit_wall > 0.,
+        );
+        if state != anim.state {
+            anim.state = state;
+            anim.frame = 0;
+            anim.timer.reset();
+        } else if anim.timer.tick(time.delta()).just_finished() {
+            anim.frame = if state.once() {
+                (anim.frame + 1).min(FRAMES - 1)
+            } else {
+                (anim.frame + 1) % FRAMES
+            };
+        }
+        sprite.index = (state.row() * FRAMES + anim.frame) as u32;
+
+        let target = player
+            .hooked
+            .and_then(|hooked| q_mine.iter().find(|(m, _)| m.id == hooked))
+            .map(|(_, m_t)| {
+                let rope = m_t.translation - transform.translation;
+                (-rope.x).atan2(rope.y)
+            })
+            .unwrap_or(0.);
+        // the short way round
+        let turn = (target - anim.angle + PI).rem_euclid(TAU) - PI;
+        anim.angle += turn * (1. - (-TURN_RATE * dt).exp());
+        transform.rotation = Quat::from_rotation_z(anim.angle);
+    }
+}
@@ -0,0 +1,299 @@
+// Everything the game loads from disk, loaded once up front while a
+// progress bar fills
+
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+use bevy_kira_audio::AudioSource;
+
+use crate::animation::{AnimState, FRAMES};
+use crate::level::PickupKind;
+use crate::physics::{MineKind, PLAYER_SIZE};
+use crate::{AppState, PlayerId, PLAYER_TINTS};
+
+pub(crate) struct GameAssets {
+    pub player: Handle<Texture>,
+    pub player_sheet: Handle<Texture>,
+    /// Frames of player_sheet, see AnimState
+    pub player_atlas: Handle<TextureAtlas>,
+    pub mine: Handle<Texture>,
+    pub font: Handle<Font>,
+    pub music: Handle<AudioSource>,
+    pub hook_sound: Handle<AudioSource>,
+    pub wall_sound: Handle<AudioSource>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let player_sheet: Handle<Texture> = world
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .load("player_sheet.png");
+        let player_atlas = world
+            .get_resource_mut::<Assets<TextureAtlas>>()
+            .unwrap()
+            .add(TextureAtlas::from_grid(
+                player_sheet.clone(),
+                Vec2::splat(PLAYER_SIZE),
+                FRAMES,
+                AnimState::ALL.len(),
+            ));
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        GameAssets {
+            player: asset_server.load("player.png"),
+            player_sheet,
+            player_atlas,
+            mine: asset_server.load("mine.png"),
+            font: asset_server.load("vcr.ttf"),
+            music: asset_server.load("music.ogg"),
+            hook_sound: asset_server.load("sfx100v2_air_02.ogg"),
+            wall_sound: asset_server.load("sfx100v2_metal_01.ogg"),
+        }
+    }
+}
+
+impl GameAssets {
+    /// Every file to wait for, with its name for the error message
+    fn files(&self) -> [(HandleId, &'static str); 7] {
+        [
+            (self.player.id, "player.png"),
+            (self.player_sheet.id, "player_sheet.png"),
+            (self.mine.id, "mine.png"),
+            (self.font.id, "vcr.ttf"),
+            (self.music.id, "music.ogg"),
+            (self.hook_sound.id, "sfx100v2_air_02.ogg"),
+            (self.wall_sound.id, "sfx100v2_metal_01.ogg"),
+        ]
+    }
+}
+
+/// One material per look a mine can have, swapped by handle instead of recolored
+pub(crate) struct MineMaterials {
+    // indexed by mine_material_index
+    materials: Vec<Handle<ColorMaterial>>,
+}
+
+pub(crate) const MINE_KINDS: [MineKind; 3] = [MineKind::Normal, MineKind::Heavy, MineKind::Boost];
+
+fn mine_material_index(kind: MineKind, selected: bool, hooked: bool) -> usize {
+    let kind = MINE_KINDS.iter().position(|k| *k == kind).unwrap_or(0);
+    kind * 4 + selected as usize * 2 + hooked as usize
+}
+
+/// Kinds are told apart by red, selected mines glow green and hooked ones blue
+pub(crate) fn mine_color(kind: MineKind, selected: bool, hooked: bool) -> Color {
+    let r = match kind {
+        MineKind::Normal => 1.,
+        MineKind::Heavy => 0.4,
+        MineKind::Boost => 4.,
+    };
+    let g = if selected { 4. } else { 1. };
+    let b = if hooked { 4. } else { 1. };
+    Color::rgb(r, g, b)
+}
+
+impl FromWorld for MineMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.get_resource::<GameAssets>().unwrap().mine.clone();
+        let mut assets = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        MineMaterials::new(texture, &mut assets)
+    }
+}
+
+impl MineMaterials {
+    pub(crate) fn new(texture: Handle<Texture>, assets: &mut Assets<ColorMaterial>) -> Self {
+        let mut materials = Vec::new();
+        for kind in MINE_KINDS.iter() {
+            for &selected in [false, true].iter() {
+                for &hooked in [false, true].iter() {
+                    materials.push(assets.add(ColorMaterial::modulated_texture(
+                        texture.clone(),
+                        mine_color(*kind, selected, hooked),
+                    )));
+                }
+            }
+        }
+        MineMaterials { materials }
+    }
+
+    pub fn get(&self, kind: MineKind, selected: bool, hooked: bool) -> Handle<ColorMaterial> {
+        self.materials[mine_material_index(kind, selected, hooked)].clone()
+    }
+}
+
+/// What levels bring besides mines, shared by every level that is played
+pub(crate) struct LevelMaterials {
+    pub hazard: Handle<ColorMaterial>,
+    pub boost: Handle<ColorMaterial>,
+    pub feather: Handle<ColorMaterial>,
+    pub finish: Handle<ColorMaterial>,
+}
+
+impl FromWorld for LevelMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        LevelMaterials {
+            hazard: materials.add(Color::rgb(0.9, 0.1, 0.1).into()),
+            boost: materials.add(Color::rgb(1.0, 0.8, 0.1).into()),
+            feather: materials.add(Color::rgb(1.0, 1.0, 1.0).into()),
+            finish: materials.add(Color::rgb(0.1, 0.1, 0.1).into()),
+        }
+    }
+}
+
+impl LevelMaterials {
+    pub fn pickup(&self, kind: PickupKind) -> Handle<ColorMaterial> {
+        match kind {
+            PickupKind::Boost => self.boost.clone(),
+            PickupKind::Feather => self.feather.clone(),
+        }
+    }
+}
+
+// the personal best ghost is see-through
+const GHOST_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+
+/// Ropes of every player and the ghost, made once instead of every run
+pub(crate) struct PlayerMaterials {
+    // indexed like PLAYER_TINTS
+    lines: Vec<Handle<ColorMaterial>>,
+    pub ghost: Handle<ColorMaterial>,
+    pub ghost_line: Handle<ColorMaterial>,
+}
+
+impl FromWorld for PlayerMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.get_resource::<GameAssets>().unwrap().player.clone();
+        let mut assets = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        PlayerMaterials::new(texture, &mut assets)
+    }
+}
+
+impl PlayerMaterials {
+    pub(crate) fn new(texture: Handle<Texture>, assets: &mut Assets<ColorMaterial>) -> Self {
+        PlayerMaterials {
+            lines: PLAYER_TINTS
+                .iter()
+                .map(|tint| assets.add((*tint).into()))
+                .collect(),
+            ghost: assets.add(ColorMaterial::modulated_texture(texture, GHOST_COLOR)),
+            ghost_line: assets.add(GHOST_COLOR.into()),
+        }
+    }
+
+    pub fn line(&self, id: PlayerId) -> Handle<ColorMaterial> {
+        self.lines[id.0 as usize % self.lines.len()].clone()
+    }
+}
+
+struct LoadingScreen;
+
+struct LoadingBar;
+
+// lists the files that failed, empty while all is well
+struct LoadingError;
+
+pub(crate) fn setup_loading(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    assets: Res<GameAssets>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                // top to bottom
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(300.0), Val::Px(20.0)),
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::rgb(0.5, 0.5, 1.0).into()),
+                            ..Default::default()
+                        })
+                        .insert(LoadingBar);
+                });
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(8.0)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 24.0,
+                            color: Color::rgb(0.9, 0.1, 0.1),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(LoadingError);
+        });
+}
+
+/// Fill the bar as assets come in, start the game once all of them are there,
+/// and stay here saying which ones are missing if any failed
+pub(crate) fn loading_system(
+    mut state: ResMut<State<AppState>>,
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut q_bar: Query<&mut Style, With<LoadingBar>>,
+    mut q_error: Query<&mut Text, With<LoadingError>>,
+) {
+    let files = assets.files();
+    let mut loaded = 0;
+    let mut failed = Vec::new();
+    for (id, name) in files.iter() {
+        match asset_server.get_load_state(*id) {
+            LoadState::Loaded => loaded += 1,
+            LoadState::Failed => failed.push(*name),
+            _ => {}
+        }
+    }
+    for mut style in q_bar.iter_mut() {
+        style.size.width = Val::Percent(100.0 * loaded as f32 / files.len() as f32);
+    }
+    if !failed.is_empty() {
+        let message = format!("Could not load {}", failed.join(", "));
+        for mut text in q_error.iter_mut() {
+            if text.sections[0].value != message {
+                warn!("{}", message);
+                text.sections[0].value = message.clone();
+            }
+        }
+    } else if loaded == files.len() {
+        state.set(AppState::Game).unwrap();
+    }
+}
+
+pub(crate) fn cleanup_loading(
+    mut commands: Commands,
+    q_screen: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in q_screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+use crate::bot::{self, Action, Bot, Observation, Strategy};
+use crate::physics::Physics;
+use crate::sim::{DeathCause, Simulation};
+
+/// How one bot run went
+#[derive(Debug, Clone, Serialize)]
+pub struct RunStats {
+    pub seed: u64,
+    pub height: f32,
+    pub ticks: u32,
+    /// None if the bot was still alive when time ran out
+    pub death: Option<DeathCause>,
+    pub hooks: u32,
+}
+
+/// Let a bot play an endless run of `seed` for at most `max_ticks`
+pub fn play(seed: u64, strategy: Strategy, physics: &Physics, max_ticks: u32) -> RunStats {
+    let mut sim = Simulation::endless(seed, *physics);
+    let mut bot = Bot::new(strategy);
+    let mut hooks = 0;
+    while !sim.over() && sim.tick < max_ticks {
+        let action = bot.act(&Observation::of(&sim, 0), physics);
+        if let Action::Hook(_) = action {
+            hooks += 1;
+        }
+        bot::apply(&mut sim, 0, action);
+        sim.step();
+    }
+    let player = &sim.players[0];
+    RunStats {
+        seed,
+        height: player.maxheight,
+        ticks: sim.tick,
+        death: player.death,
+        hooks,
+    }
+}
+
+/// The distribution of a batch of runs
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    pub runs: usize,
+    pub mean_height: f32,
+    pub median_height: f32,
+    pub p95_height: f32,
+    pub fell: usize,
+    pub hazard: usize,
+    /// Still alive when time ran out
+    pub survived: usize,
+    pub hooks_per_1000: f32,
+}
+
+impl Summary {
+    pub const CSV_HEADER: &'static str =
+        "runs,mean_height,median_height,p95_height,fell,hazard,survived,hooks_per_1000";
+
+    pub fn of(runs: &[RunStats]) -> Self {
+        if runs.is_empty() {
+            return Self::default();
+        }
+        let mut heights: Vec<f32> = runs.iter().map(|r| r.height).collect();
+        heights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let total_height: f32 = heights.iter().sum();
+        let total_hooks: u32 = runs.iter().map(|r| r.hooks).sum();
+        let died = |cause| runs.iter().filter(|r| r.death == Some(cause)).count();
+        Self {
+            runs: runs.len(),
+            mean_height: total_height / runs.len() as f32,
+            median_height: percentile(&heights, 50.),
+            p95_height: percentile(&heights, 95.),
+            fell: died(DeathCause::Fell),
+            hazard: died(DeathCause::Hazard),
+            survived: runs.iter().filter(|r| r.death.is_none()).count(),
+            hooks_per_1000: if total_height > 0. {
+                total_hooks as f32 * 1000. / total_height
+            } else {
+                0.
+            },
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{:.1},{:.1},{:.1},{},{},{},{:.2}",
+            self.runs,
+            self.mean_height,
+            self.median_height,
+            self.p95_height,
+            self.fell,
+            self.hazard,
+            self.survived,
+            self.hooks_per_1000
+        )
+    }
+}
+
+/// Nearest rank percentile of already sorted values
+fn percentile(sorted: &[f32], p: f32) -> f32 {
+    let rank = (p / 100. * sorted.len() as f32).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -0,0 +1,41 @@
+//! The headless game as a reinforcement learning environment, speaking
+//! JSON lines on stdin and stdout so trainers in any language can use it.
+//!
+//! upwards-env [--config physics.json]
+//!
+//! {"reset": {"seed": 3}}     -> an Observation
+//! {"step": "noop"}           -> {"observation": ..., "reward": ..., "done": ...}
+//! {"step": "release"}
+//! {"step": {"hook": 0}}      hooks the first mine of the last observation
+
+use std::io::{self, BufRead, Write};
+
+use upwards::env::Env;
+use upwards::physics::Physics;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let physics = match args.as_slice() {
+        [] => Physics::default(),
+        [flag, path] if flag == "--config" => {
+            let json = std::fs::read_to_string(path).expect("Can't read the config");
+            serde_json::from_str(&json).expect("Bad config")
+        }
+        _ => {
+            eprintln!("usage: upwards-env [--config physics.json]");
+            std::process::exit(2);
+        }
+    };
+    let mut env = Env::new(physics);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Can't read stdin");
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(out, "{}", env.handle(&line)).expect("Can't write stdout");
+        // trainers wait for the answer before sending the next line
+        out.flush().expect("Can't write stdout");
+    }
+}
@@ -0,0 +1,119 @@
+//! Plays many headless runs with a bot and prints how high they got, to
+//! compare physics changes without playing them by hand.
+//!
+//! upwards-sim [--runs N] [--seed S] [--strategy greedy|lookahead]
+//!             [--config physics.json] [--minutes M] [--format csv|json]
+//!
+//! The config file holds any fields of Physics, missing ones keep the
+//! values the game ships with: {"gravity": 0.1, "upward": 0.3}
+
+use std::process::exit;
+
+use upwards::balance::{self, Summary};
+use upwards::bot::Strategy;
+use upwards::physics::{Physics, TIME_STEP};
+
+const FLAGS: [&str; 6] = [
+    "--runs",
+    "--seed",
+    "--strategy",
+    "--config",
+    "--minutes",
+    "--format",
+];
+
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Args {
+    runs: u64,
+    seed: u64,
+    strategy: Strategy,
+    physics: Physics,
+    max_ticks: u32,
+    format: Format,
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!(
+        "usage: upwards-sim [--runs N] [--seed S] [--strategy greedy|lookahead] \
+         [--config physics.json] [--minutes M] [--format csv|json]"
+    );
+    exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        runs: 100,
+        seed: 0,
+        strategy: Strategy::Greedy,
+        physics: Physics::default(),
+        max_ticks: (120. / TIME_STEP) as u32,
+        format: Format::Csv,
+    };
+    let mut it = std::env::args().skip(1);
+    while let Some(flag) = it.next() {
+        if !FLAGS.contains(&flag.as_str()) {
+            usage(&format!("unknown flag {}", flag));
+        }
+        let value = it
+            .next()
+            .unwrap_or_else(|| usage(&format!("{} needs a value", flag)));
+        match flag.as_str() {
+            "--runs" => args.runs = value.parse().unwrap_or_else(|_| usage("bad --runs")),
+            "--seed" => args.seed = value.parse().unwrap_or_else(|_| usage("bad --seed")),
+            "--strategy" => {
+                args.strategy = match value.as_str() {
+                    "greedy" => Strategy::Greedy,
+                    "lookahead" => Strategy::Lookahead,
+                    _ => usage("bad --strategy"),
+                }
+            }
+            "--config" => {
+                let json = std::fs::read_to_string(&value)
+                    .unwrap_or_else(|e| usage(&format!("can't read {}: {}", value, e)));
+                args.physics = serde_json::from_str(&json)
+                    .unwrap_or_else(|e| usage(&format!("bad config {}: {}", value, e)));
+            }
+            "--minutes" => {
+                let minutes: f32 = value.parse().unwrap_or_else(|_| usage("bad --minutes"));
+                args.max_ticks = (minutes * 60. / TIME_STEP) as u32;
+            }
+            "--format" => {
+                args.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => usage("bad --format"),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    args
+}
+
+fn main() {
+    let args = parse_args();
+    let end = args
+        .seed
+        .checked_add(args.runs)
+        .unwrap_or_else(|| usage("--seed plus --runs is too large"));
+    // seeds follow each other so the same flags always play the same runs
+    let runs: Vec<_> = (args.seed..end)
+        .map(|seed| balance::play(seed, args.strategy, &args.physics, args.max_ticks))
+        .collect();
+    let summary = Summary::of(&runs);
+    match args.format {
+        Format::Csv => {
+            println!("{}", Summary::CSV_HEADER);
+            println!("{}", summary.to_csv());
+        }
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).expect("Summaries are always valid JSON")
+        ),
+    }
+}
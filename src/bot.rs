@@ -0,0 +1,192 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::physics::{on_screen, Physics};
+use crate::sim::{SimMine, SimPlayer, Simulation};
+
+// a bot makes up its mind this often, about as quick as a human
+const DECISION_TICKS: u32 = 5;
+// a higher mine has to be this much higher to be worth switching to
+const SWITCH_MARGIN: f32 = 60.;
+// how long the lookahead holds its first choice, and how far it looks in total
+const LOOKAHEAD_HOLD: u32 = 8;
+const LOOKAHEAD_TICKS: u32 = 45;
+// dying is worse than any height
+const DEATH_PENALTY: f32 = 10_000.;
+
+/// The same things a human can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Wait,
+    Hook(u32),
+    Release,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Hang on the highest mine on screen
+    Greedy,
+    /// Try every choice on a copy of the world and keep the best one
+    Lookahead,
+}
+
+/// What a bot gets to see, the same for the game and the headless simulation
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub player: SimPlayer,
+    /// Height the screen follows
+    pub top: f32,
+    pub mines: Vec<SimMine>,
+}
+
+impl Observation {
+    pub fn of(sim: &Simulation, player: u8) -> Self {
+        Self {
+            player: sim.players[player as usize].clone(),
+            top: sim.top(),
+            mines: sim.mines.clone(),
+        }
+    }
+
+    fn mine(&self, id: u32) -> Option<&SimMine> {
+        self.mines.iter().find(|m| m.id == id)
+    }
+
+    fn grabbable(&self) -> impl Iterator<Item = &SimMine> {
+        let top = self.top;
+        self.mines
+            .iter()
+            .filter(move |m| on_screen(top, m.position.y))
+    }
+}
+
+/// Plays in place of a human
+#[derive(Debug, Clone)]
+pub struct Bot {
+    pub strategy: Strategy,
+    // ticks until the next decision
+    wait: u32,
+}
+
+impl Bot {
+    pub fn new(strategy: Strategy) -> Self {
+        Self { strategy, wait: 0 }
+    }
+
+    /// Call once per tick, before the tick
+    pub fn act(&mut self, obs: &Observation, physics: &Physics) -> Action {
+        if self.wait > 0 {
+            self.wait -= 1;
+            return Action::Wait;
+        }
+        self.wait = DECISION_TICKS - 1;
+        match self.strategy {
+            Strategy::Greedy => greedy(obs),
+            Strategy::Lookahead => lookahead(obs, physics),
+        }
+    }
+}
+
+fn higher(a: &&SimMine, b: &&SimMine) -> Ordering {
+    a.position
+        .y
+        .partial_cmp(&b.position.y)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Hook the highest mine above, let go once it is passed
+fn greedy(obs: &Observation) -> Action {
+    let p = &obs.player;
+    let target = obs
+        .grabbable()
+        .filter(|m| m.position.y > p.position.y)
+        .max_by(higher);
+    match (p.hooked.and_then(|id| obs.mine(id)), target) {
+        (Some(hooked), Some(target)) if target.position.y > hooked.position.y + SWITCH_MARGIN => {
+            Action::Hook(target.id)
+        }
+        // keep climbing
+        (Some(hooked), _) if hooked.position.y > p.position.y => Action::Wait,
+        // fly on and grab the next one on the way down
+        (Some(_), _) => Action::Release,
+        (None, Some(target)) if p.velocity.y < 0. => Action::Hook(target.id),
+        (None, _) => Action::Wait,
+    }
+}
+
+/// Hold every possible choice for a while, play greedy after that, and pick
+/// what got highest without dying
+fn lookahead(obs: &Observation, physics: &Physics) -> Action {
+    let mut choices = vec![Action::Wait, Action::Release];
+    choices.extend(obs.grabbable().map(|m| Action::Hook(m.id)));
+    choices
+        .into_iter()
+        .map(|choice| (choice, rollout(obs, physics, choice)))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+        .map(|(choice, _)| choice)
+        .unwrap_or(Action::Wait)
+}
+
+fn rollout(obs: &Observation, physics: &Physics, first: Action) -> f32 {
+    // the other players aren't played out, but the screen still follows the top one
+    let mut sim = Simulation::snapshot(
+        *physics,
+        vec![obs.player.clone()],
+        obs.mines.clone(),
+        obs.top,
+    );
+    apply(&mut sim, 0, first);
+    let mut bot = Bot::new(Strategy::Greedy);
+    bot.wait = LOOKAHEAD_HOLD;
+    for _ in 0..LOOKAHEAD_TICKS {
+        let action = bot.act(&Observation::of(&sim, 0), physics);
+        apply(&mut sim, 0, action);
+        sim.step();
+        if sim.over() {
+            return sim.players[0].maxheight - DEATH_PENALTY;
+        }
+    }
+    let p = &sim.players[0];
+    // where it would end up flying on, speed is worth as much as height
+    let apex = p.velocity.y.max(0.).powi(2) / (2. * physics.gravity.max(0.01));
+    p.position.y.max(p.maxheight) + apex
+}
+
+/// Hook or release like a click would
+pub fn apply(sim: &mut Simulation, player: u8, action: Action) {
+    match action {
+        Action::Wait => {}
+        Action::Hook(id) => sim.hook(player, Some(id)),
+        Action::Release => sim.hook(player, None),
+    }
+}
+
+/// Let bots play a simulation until everyone is dead or `max_ticks` ran out
+pub fn autoplay(sim: &mut Simulation, bots: &mut [Bot], max_ticks: u32) {
+    while !sim.over() && sim.tick < max_ticks {
+        for (i, bot) in bots.iter_mut().enumerate() {
+            let action = bot.act(&Observation::of(sim, i as u8), &sim.physics);
+            apply(sim, i as u8, action);
+        }
+        sim.step();
+    }
+}
+
+/// Mean height a strategy reaches on endless runs of the given seeds
+pub fn average_height(strategy: Strategy, seeds: &[u64], physics: &Physics, max_ticks: u32) -> f32 {
+    if seeds.is_empty() {
+        return 0.;
+    }
+    let total: f32 = seeds
+        .iter()
+        .map(|seed| {
+            let mut sim = Simulation::endless(*seed, *physics);
+            autoplay(&mut sim, &mut [Bot::new(strategy)], max_ticks);
+            sim.players[0].maxheight
+        })
+        .sum();
+    total / seeds.len() as f32
+}
@@ -0,0 +1,371 @@
+// Follows the highest player with a bit of delay, looks ahead where it is
+// going, shakes on hits and zooms out at speed. The board is scaled to fit
+// the window, with bars around it where the window is wider or taller
+
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::physics::{Physics, GAME_BOARD, TIME_STEP};
+use crate::settings::Settings;
+use crate::sim::GameEvent;
+use crate::{top_height, AppState, MainCamera, Player};
+
+// seconds the smoothing roughly lags behind
+const SMOOTH_TIME: f32 = 0.15;
+// the camera leads by where the player is this many seconds from now
+const LOOK_AHEAD_TIME: f32 = 0.2;
+const MAX_LOOK_AHEAD: f32 = 120.;
+// trauma added by hits, it fades by TRAUMA_DECAY per second
+const WALL_TRAUMA: f32 = 0.3;
+const DEATH_TRAUMA: f32 = 0.8;
+const TRAUMA_DECAY: f32 = 1.5;
+// how far the camera moves at full trauma
+const MAX_SHAKE: f32 = 12.;
+// above this many units per second the view starts to widen, up to MAX_ZOOM
+const ZOOM_SPEED: f32 = 600.;
+const ZOOM_RANGE: f32 = 1500.;
+const MAX_ZOOM: f32 = 1.4;
+// room next to the walls that is still part of the board
+const WALL_MARGIN: f32 = 16.;
+// letterbox bars reach this far out, past any window
+const BAR_SIZE: f32 = 100_000.;
+// in front of everything in the world
+const BAR_Z: f32 = 50.;
+
+/// Where the camera is headed, without the shake
+pub(crate) struct CameraRig {
+    y: f32,
+    y_velocity: f32,
+    zoom: f32,
+    zoom_velocity: f32,
+    trauma: f32,
+    /// Jump straight to the target next frame, for the start of a run
+    snap: bool,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        CameraRig {
+            y: 0.,
+            y_velocity: 0.,
+            zoom: 1.,
+            zoom_velocity: 0.,
+            trauma: 0.,
+            snap: true,
+        }
+    }
+}
+
+/// World units per window pixel, without zoom
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ViewScale(pub(crate) f32);
+
+impl Default for ViewScale {
+    fn default() -> Self {
+        ViewScale(1.)
+    }
+}
+
+/// Top, bottom, left and right of the board
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LetterboxBar(Vec2);
+
+/// Size of everything that should be on screen
+pub(crate) fn board_size(physics: &Physics) -> Vec2 {
+    Vec2::new(
+        (physics.half_width + WALL_MARGIN) * 2.,
+        GAME_BOARD.3 - GAME_BOARD.2,
+    )
+}
+
+/// World units per pixel that fit all of `board` into `window`
+pub(crate) fn fit_scale(window: Vec2, board: Vec2) -> f32 {
+    if window.x <= 0. || window.y <= 0. {
+        // minimized
+        return 1.;
+    }
+    (board.x / window.x).max(board.y / window.y)
+}
+
+/// Undo the camera for a position in window pixels, from the bottom left
+pub(crate) fn screen_to_world(screen: Vec2, window: Vec2, camera: &Transform) -> Vec2 {
+    // the orthographic projection is in pixels from the center
+    let centered = screen - window / 2.0;
+    let world = camera.compute_matrix() * centered.extend(0.0).extend(1.0);
+    Vec2::new(world.x, world.y)
+}
+
+/// Half the size of what is on screen around the camera, in world units: the
+/// whole window, or only the board when the letterbox bars cover the rest
+pub(crate) fn visible_half_size(
+    window: Vec2,
+    board: Vec2,
+    fit_to_window: bool,
+    camera: &Transform,
+) -> Vec2 {
+    let half = screen_to_world(window, window, camera) - camera.translation.truncate();
+    if !fit_to_window {
+        return half;
+    }
+    let zoom = camera.scale.x / fit_scale(window, board);
+    half.min(board / 2. * zoom)
+}
+
+/// Critically damped spring towards `target`, never overshoots
+pub(crate) fn smooth_damp(
+    current: f32,
+    target: f32,
+    velocity: &mut f32,
+    smooth_time: f32,
+    dt: f32,
+) -> f32 {
+    let omega = 2. / smooth_time.max(0.0001);
+    let x = omega * dt;
+    let exp = 1. / (1. + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = current - target;
+    let temp = (*velocity + omega * change) * dt;
+    *velocity = (*velocity - omega * temp) * exp;
+    let mut result = target + (change + temp) * exp;
+    // stop exactly on target instead of passing it
+    if (target - current > 0.) == (result > target) {
+        result = target;
+        *velocity = 0.;
+    }
+    result
+}
+
+/// How far ahead of the player to look, `vy` in units per second
+pub(crate) fn look_ahead(vy: f32) -> f32 {
+    (vy * LOOK_AHEAD_TIME).clamp(-MAX_LOOK_AHEAD, MAX_LOOK_AHEAD)
+}
+
+/// Camera scale for a speed in units per second, 1 is no zoom
+pub(crate) fn zoom_for_speed(speed: f32) -> f32 {
+    1. + ((speed - ZOOM_SPEED) / ZOOM_RANGE).clamp(0., 1.) * (MAX_ZOOM - 1.)
+}
+
+/// No zoom or shake left over for the menus, and the next run starts with
+/// the camera right on the players
+pub(crate) fn reset_camera(
+    mut rig: ResMut<CameraRig>,
+    view: Res<ViewScale>,
+    mut q_cam: Query<&mut Transform, With<MainCamera>>,
+) {
+    *rig = CameraRig::default();
+    if let Ok(mut cam_t) = q_cam.single_mut() {
+        cam_t.translation.x = 0.;
+        cam_t.scale = Vec3::new(view.0, view.0, 1.);
+    }
+}
+
+pub(crate) fn setup_letterbox(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let material = materials.add(Color::rgb(0.1, 0.1, 0.1).into());
+    for side in [Vec2::Y, -Vec2::Y, Vec2::X, -Vec2::X].iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: material.clone(),
+                sprite: Sprite::new(Vec2::splat(BAR_SIZE)),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: false,
+                },
+                ..Default::default()
+            })
+            .insert(LetterboxBar(*side));
+    }
+}
+
+/// Keep the scale fitting the window, outside of runs the camera is set right away
+pub(crate) fn view_scale_system(
+    windows: Res<Windows>,
+    physics: Res<Physics>,
+    settings: Res<Settings>,
+    state: Res<State<AppState>>,
+    mut view: ResMut<ViewScale>,
+    mut q_cam: Query<&mut Transform, With<MainCamera>>,
+) {
+    let scale = match windows.get_primary() {
+        Some(wnd) if settings.fit_to_window => {
+            fit_scale(Vec2::new(wnd.width(), wnd.height()), board_size(&physics))
+        }
+        _ => 1.,
+    };
+    if view.0 != scale {
+        view.0 = scale;
+    }
+    if *state.current() != AppState::Game {
+        if let Ok(mut cam_t) = q_cam.single_mut() {
+            cam_t.scale = Vec3::new(scale, scale, 1.);
+        }
+    }
+}
+
+/// Cover everything around the board, only while playing
+pub(crate) fn letterbox_system(
+    physics: Res<Physics>,
+    settings: Res<Settings>,
+    view: Res<ViewScale>,
+    state: Res<State<AppState>>,
+    q_cam: Query<&Transform, (With<MainCamera>, Without<LetterboxBar>)>,
+    mut q_bar: Query<(&LetterboxBar, &mut Transform, &mut Visible)>,
+) {
+    let shown = settings.fit_to_window && *state.current() == AppState::Game;
+    let cam_t = match q_cam.single() {
+        Ok(cam_t) => cam_t,
+        Err(_) => return,
+    };
+    // the bars stay put on screen while the camera zooms
+    let half = board_size(&physics) / 2. * cam_t.scale.x / view.0;
+    for (bar, mut bar_t, mut visible) in q_bar.iter_mut() {
+        if visible.is_visible != shown {
+            visible.is_visible = shown;
+        }
+        let offset = bar.0 * (half + Vec2::splat(BAR_SIZE / 2.));
+        bar_t.translation = (cam_t.translation.truncate() + offset).extend(BAR_Z);
+    }
+}
+
+pub(crate) fn camera_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    view: Res<ViewScale>,
+    mut rig: ResMut<CameraRig>,
+    mut events: EventReader<GameEvent>,
+    q_player: Query<&Player>,
+    mut q_cam: Query<&mut Transform, With<MainCamera>>,
+) {
+    let dt = time.delta_seconds();
+    for event in events.iter() {
+        match event {
+            GameEvent::WallBounce { .. } => rig.trauma += WALL_TRAUMA,
+            GameEvent::Died { .. } => rig.trauma += DEATH_TRAUMA,
+            _ => {}
+        }
+    }
+    rig.trauma = (rig.trauma - TRAUMA_DECAY * dt).clamp(0., 1.);
+
+    let top = match top_height(q_player.iter().map(|p| p.maxheight)) {
+        Some(top) => top,
+        None => return,
+    };
+    // looking ahead and zooming follow the highest player that is still alive
+    let leader = q_player.iter().filter(|p| !p.dead()).max_by(|a, b| {
+        a.maxheight
+            .partial_cmp(&b.maxheight)
+            .unwrap_or(Ordering::Equal)
+    });
+    // player velocities are per tick
+    let velocity = leader.map_or(Vec3::ZERO, |p| p.velocity / TIME_STEP);
+
+    let mut target = top;
+    if settings.look_ahead {
+        target += look_ahead(velocity.y);
+    }
+    let target_zoom = if settings.speed_zoom {
+        zoom_for_speed(velocity.length())
+    } else {
+        1.
+    };
+    if rig.snap || !settings.camera_smoothing {
+        rig.y = target;
+        rig.y_velocity = 0.;
+        rig.snap = false;
+    } else {
+        let (y, mut y_velocity) = (rig.y, rig.y_velocity);
+        rig.y = smooth_damp(y, target, &mut y_velocity, SMOOTH_TIME, dt);
+        rig.y_velocity = y_velocity;
+    }
+    // zooming always eases, jumping would be jarring
+    let (zoom, mut zoom_velocity) = (rig.zoom, rig.zoom_velocity);
+    rig.zoom = smooth_damp(zoom, target_zoom, &mut zoom_velocity, SMOOTH_TIME * 4., dt);
+    rig.zoom_velocity = zoom_velocity;
+
+    let mut shake = Vec2::ZERO;
+    if settings.screen_shake && rig.trauma > 0. {
+        let mut rng = rand::thread_rng();
+        let amount = MAX_SHAKE * rig.trauma * rig.trauma;
+        shake = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * amount;
+    }
+
+    if let Ok(mut cam_t) = q_cam.single_mut() {
+        cam_t.translation.x = shake.x;
+        cam_t.translation.y = rig.y + shake.y;
+        let scale = view.0 * rig.zoom;
+        cam_t.scale = Vec3::new(scale, scale, 1.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> Vec2 {
+        board_size(&Physics::default())
+    }
+
+    #[test]
+    fn fit_scale_wide_window() {
+        // the height decides, bars left and right
+        let window = Vec2::new(4. * board().x, board().y / 2.);
+        assert_eq!(fit_scale(window, board()), 2.);
+    }
+
+    #[test]
+    fn fit_scale_tall_window() {
+        // the width decides, bars above and below
+        let window = Vec2::new(board().x / 2., 4. * board().y);
+        assert_eq!(fit_scale(window, board()), 2.);
+    }
+
+    #[test]
+    fn fit_scale_minimized_window() {
+        assert_eq!(fit_scale(Vec2::ZERO, board()), 1.);
+        assert_eq!(fit_scale(Vec2::new(800., 0.), board()), 1.);
+    }
+
+    #[test]
+    fn window_corners_map_to_board_edges() {
+        let window = board() / 2.;
+        let scale = fit_scale(window, board());
+        let mut camera = Transform::from_xyz(30., 1000., 0.);
+        camera.scale = Vec3::new(scale, scale, 1.);
+        let center = camera.translation.truncate();
+        assert_eq!(
+            screen_to_world(Vec2::ZERO, window, &camera),
+            center - board() / 2.
+        );
+        assert_eq!(
+            screen_to_world(window, window, &camera),
+            center + board() / 2.
+        );
+        assert_eq!(screen_to_world(window / 2., window, &camera), center);
+    }
+
+    #[test]
+    fn visible_area_without_fitting_is_the_window() {
+        let window = Vec2::new(1600., 400.);
+        let camera = Transform::from_xyz(0., 1000., 0.);
+        assert_eq!(
+            visible_half_size(window, board(), false, &camera),
+            window / 2.
+        );
+    }
+
+    #[test]
+    fn visible_area_with_fitting_stops_at_the_bars() {
+        // bars left and right, the height is all board
+        let window = Vec2::new(4. * board().x, board().y / 2.);
+        let scale = fit_scale(window, board());
+        let mut camera = Transform::from_xyz(0., 1000., 0.);
+        camera.scale = Vec3::new(scale, scale, 1.);
+        assert_eq!(
+            visible_half_size(window, board(), true, &camera),
+            board() / 2.
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use bevy::prelude::*;
+
+use crate::assets::{GameAssets, LevelMaterials, MineMaterials};
+use crate::level::{Hazard, Level, LevelMine, Pickup, PickupKind};
+use crate::physics::{MineKind, GAME_BOARD};
+use crate::{cursor_to_world, storage, AppState, GameMode, MainCamera};
+
+// everything snaps to this grid
+const GRID: f32 = 20.0;
+// world units per second the camera pans
+const PAN_SPEED: f32 = 600.0;
+const HAZARD_SIZE: (f32, f32) = (100.0, 24.0);
+// how close the cursor has to be to grab a mine or pickup
+const GRAB_RADIUS: f32 = 16.0;
+const SAVE_KEY: &str = "editor_level";
+
+/// What a left click puts into the level
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tool {
+    Mine(MineKind),
+    Hazard,
+    Pickup(PickupKind),
+}
+
+/// Something in the level under the cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Item {
+    Mine(usize),
+    Hazard(usize),
+    Pickup(usize),
+}
+
+/// The level being edited, kept while play-testing it
+pub(crate) struct EditorState {
+    pub(crate) level: Level,
+    /// Camera height the last play-test started from
+    pub(crate) playtest_from: f32,
+    tool: Tool,
+    dragging: Option<Item>,
+}
+
+impl Default for EditorState {
+    fn default() -> Self {
+        Self {
+            level: Level {
+                name: "Custom".to_string(),
+                mines: Vec::new(),
+                hazards: Vec::new(),
+                pickups: Vec::new(),
+                finish: 2000.0,
+                par_time: 30.0,
+            },
+            playtest_from: 0.0,
+            tool: Tool::Mine(MineKind::Normal),
+            dragging: None,
+        }
+    }
+}
+
+// Marker for the sprites showing the level
+struct EditorItem;
+// Marker for the help text
+struct EditorHelp;
+
+fn snap(p: Vec2) -> Vec2 {
+    (p / GRID).round() * GRID
+}
+
+fn item_at(level: &Level, p: Vec2) -> Option<Item> {
+    for (i, m) in level.mines.iter().enumerate() {
+        if Vec2::new(m.x, m.y).distance(p) < GRAB_RADIUS {
+            return Some(Item::Mine(i));
+        }
+    }
+    for (i, pu) in level.pickups.iter().enumerate() {
+        if Vec2::new(pu.x, pu.y).distance(p) < GRAB_RADIUS {
+            return Some(Item::Pickup(i));
+        }
+    }
+    for (i, h) in level.hazards.iter().enumerate() {
+        if (p.x - h.x).abs() <= h.width / 2. && (p.y - h.y).abs() <= h.height / 2. {
+            return Some(Item::Hazard(i));
+        }
+    }
+    None
+}
+
+fn add_item(level: &mut Level, tool: Tool, p: Vec2) -> Item {
+    match tool {
+        Tool::Mine(kind) => {
+            level.mines.push(LevelMine {
+                x: p.x,
+                y: p.y,
+                kind,
+            });
+            Item::Mine(level.mines.len() - 1)
+        }
+        Tool::Hazard => {
+            level.hazards.push(Hazard {
+                x: p.x,
+                y: p.y,
+                width: HAZARD_SIZE.0,
+                height: HAZARD_SIZE.1,
+            });
+            Item::Hazard(level.hazards.len() - 1)
+        }
+        Tool::Pickup(kind) => {
+            level.pickups.push(Pickup {
+                x: p.x,
+                y: p.y,
+                kind,
+            });
+            Item::Pickup(level.pickups.len() - 1)
+        }
+    }
+}
+
+fn item_position(level: &Level, item: Item) -> Vec2 {
+    match item {
+        Item::Mine(i) => Vec2::new(level.mines[i].x, level.mines[i].y),
+        Item::Hazard(i) => Vec2::new(level.hazards[i].x, level.hazards[i].y),
+        Item::Pickup(i) => Vec2::new(level.pickups[i].x, level.pickups[i].y),
+    }
+}
+
+fn move_item(level: &mut Level, item: Item, p: Vec2) {
+    match item {
+        Item::Mine(i) => {
+            level.mines[i].x = p.x;
+            level.mines[i].y = p.y;
+        }
+        Item::Hazard(i) => {
+            level.hazards[i].x = p.x;
+            level.hazards[i].y = p.y;
+        }
+        Item::Pickup(i) => {
+            level.pickups[i].x = p.x;
+            level.pickups[i].y = p.y;
+        }
+    }
+}
+
+fn remove_item(level: &mut Level, item: Item) {
+    match item {
+        Item::Mine(i) => {
+            level.mines.remove(i);
+        }
+        Item::Hazard(i) => {
+            level.hazards.remove(i);
+        }
+        Item::Pickup(i) => {
+            level.pickups.remove(i);
+        }
+    }
+}
+
+fn tool_name(tool: Tool) -> &'static str {
+    match tool {
+        Tool::Mine(MineKind::Normal) => "mine",
+        Tool::Mine(MineKind::Heavy) => "heavy mine",
+        Tool::Mine(MineKind::Boost) => "boost mine",
+        Tool::Hazard => "hazard",
+        Tool::Pickup(PickupKind::Boost) => "boost pickup",
+        Tool::Pickup(PickupKind::Feather) => "feather pickup",
+    }
+}
+
+fn help_text(tool: Tool) -> String {
+    format!(
+        "tool: {}  [1-6] tools  [arrows] pan  [left] place/move  [right] delete\n\
+         [F] finish here  [S] save  [L] load  [P] play-test  [backspace] menu",
+        tool_name(tool)
+    )
+}
+
+/// Drawn with the same materials as the level in the game
+fn spawn_items(
+    commands: &mut Commands,
+    materials: &LevelMaterials,
+    mine_materials: &MineMaterials,
+    level: &Level,
+) {
+    for m in level.mines.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: mine_materials.get(m.kind, false, false),
+                sprite: Sprite::new(Vec2::new(32.0, 32.0)),
+                transform: Transform::from_xyz(m.x, m.y, 1.0),
+                ..Default::default()
+            })
+            .insert(EditorItem);
+    }
+    for h in level.hazards.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.hazard.clone(),
+                sprite: Sprite::new(Vec2::new(h.width, h.height)),
+                transform: Transform::from_xyz(h.x, h.y, 0.5),
+                ..Default::default()
+            })
+            .insert(EditorItem);
+    }
+    for pu in level.pickups.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.pickup(pu.kind),
+                sprite: Sprite::new(Vec2::new(16.0, 16.0)),
+                transform: Transform::from_xyz(pu.x, pu.y, 0.5),
+                ..Default::default()
+            })
+            .insert(EditorItem);
+    }
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: materials.finish.clone(),
+            sprite: Sprite::new(Vec2::new(GAME_BOARD.1 * 2. + 32., 4.0)),
+            transform: Transform::from_xyz(0.0, level.finish, 0.5),
+            ..Default::default()
+        })
+        .insert(EditorItem);
+}
+
+pub(crate) fn setup_editor(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut editor: ResMut<EditorState>,
+) {
+    // also marks the level changed, so editor_render_system draws it
+    editor.dragging = None;
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text::with_section(
+                help_text(editor.tool),
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.15, 0.15, 0.15),
+                },
+                Default::default(),
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.0),
+                    left: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(EditorHelp);
+}
+
+/// Pan, place, drag, delete, save, load and play-test
+pub(crate) fn editor_input_system(
+    wnds: Res<Windows>,
+    btns: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut editor: ResMut<EditorState>,
+    mut app_state: ResMut<State<AppState>>,
+    mut mode: ResMut<GameMode>,
+    mut q_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let mut cam_t = q_camera.single_mut().expect("Need exactly one camera");
+
+    // pan
+    let mut pan = Vec2::ZERO;
+    if keys.pressed(KeyCode::Left) {
+        pan.x -= 1.;
+    }
+    if keys.pressed(KeyCode::Right) {
+        pan.x += 1.;
+    }
+    if keys.pressed(KeyCode::Down) {
+        pan.y -= 1.;
+    }
+    if keys.pressed(KeyCode::Up) {
+        pan.y += 1.;
+    }
+    cam_t.translation += pan.extend(0.) * PAN_SPEED * time.delta_seconds();
+
+    // tools
+    let tools = [
+        (KeyCode::Key1, Tool::Mine(MineKind::Normal)),
+        (KeyCode::Key2, Tool::Mine(MineKind::Heavy)),
+        (KeyCode::Key3, Tool::Mine(MineKind::Boost)),
+        (KeyCode::Key4, Tool::Hazard),
+        (KeyCode::Key5, Tool::Pickup(PickupKind::Boost)),
+        (KeyCode::Key6, Tool::Pickup(PickupKind::Feather)),
+    ];
+    for (key, tool) in tools.iter() {
+        if keys.just_pressed(*key) {
+            editor.tool = *tool;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::S) {
+        if let Err(e) = storage::save(SAVE_KEY, &editor.level.to_json()) {
+            warn!("Could not save level: {}", e);
+        }
+    }
+    if keys.just_pressed(KeyCode::L) {
+        match storage::load(SAVE_KEY).map(|json| Level::from_json(&json)) {
+            Some(Ok(level)) => {
+                // indices into the old level mean nothing in the new one
+                editor.dragging = None;
+                editor.level = level;
+            }
+            Some(Err(e)) => warn!("Saved level is broken: {}", e),
+            None => warn!("No saved level"),
+        }
+    }
+    if keys.just_pressed(KeyCode::P) {
+        editor.playtest_from = cam_t.translation.y;
+        *mode = GameMode::PlayTest;
+        app_state.set(AppState::Game).unwrap();
+        return;
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        app_state.set(AppState::Menu).unwrap();
+        return;
+    }
+
+    let wnd = wnds.get_primary().unwrap();
+    if let Some(pos) = cursor_to_world(wnd, &cam_t) {
+        let pos = snap(pos);
+
+        if keys.just_pressed(KeyCode::F) {
+            editor.level.finish = pos.y;
+        }
+        if btns.just_pressed(MouseButton::Left) {
+            let item = match item_at(&editor.level, pos) {
+                Some(item) => item,
+                None => {
+                    let tool = editor.tool;
+                    add_item(&mut editor.level, tool, pos)
+                }
+            };
+            editor.dragging = Some(item);
+        }
+        if let Some(item) = editor.dragging {
+            // only touch the level when something moved, so it isn't redrawn every frame
+            if item_position(&editor.level, item) != pos {
+                move_item(&mut editor.level, item, pos);
+            }
+        }
+        if btns.just_pressed(MouseButton::Right) {
+            if let Some(item) = item_at(&editor.level, pos) {
+                // removing shifts the indices of the items after it
+                editor.dragging = None;
+                remove_item(&mut editor.level, item);
+            }
+        }
+    }
+    if btns.just_released(MouseButton::Left) && editor.dragging.is_some() {
+        editor.dragging = None;
+    }
+}
+
+/// Redraw the level whenever it changed
+pub(crate) fn editor_render_system(
+    mut commands: Commands,
+    materials: Res<LevelMaterials>,
+    mine_materials: Res<MineMaterials>,
+    editor: Res<EditorState>,
+    q_items: Query<Entity, With<EditorItem>>,
+    mut q_help: Query<&mut Text, With<EditorHelp>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+    for entity in q_items.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_items(&mut commands, &materials, &mine_materials, &editor.level);
+
+    if let Ok(mut text) = q_help.single_mut() {
+        text.sections[0].value = help_text(editor.tool);
+    }
+}
+
+pub(crate) fn cleanup_editor(
+    mut commands: Commands,
+    q_items: Query<Entity, Or<(With<EditorItem>, With<EditorHelp>)>>,
+) {
+    for entity in q_items.iter() {
+        commands.entity(entity).despawn();
+    }
+}
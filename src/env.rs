@@ -0,0 +1,170 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::physics::{on_screen, MineKind, Physics, TIME_STEP};
+use crate::sim::{DeathCause, Simulation};
+
+// how many mines an observation shows at most
+pub const NEAREST_MINES: usize = 8;
+// an action is held this long, about as often as a human can click
+pub const TICKS_PER_STEP: u32 = 5;
+// episodes end after two minutes of game time
+pub const MAX_TICKS: u32 = (120. / TIME_STEP) as u32;
+
+/// What an agent can do every step, hook picks one of the observed mines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Noop,
+    Release,
+    /// Index into the mines of the last observation
+    Hook(usize),
+}
+
+/// A mine, relative to the player
+#[derive(Debug, Clone, Serialize)]
+pub struct MineView {
+    pub dx: f32,
+    pub dy: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub kind: MineKind,
+    pub hooked: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Observation {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub height: f32,
+    /// Seconds of feather left
+    pub feather: f32,
+    pub hooked: bool,
+    /// The NEAREST_MINES closest grabbable mines, closest first, may be fewer
+    pub mines: Vec<MineView>,
+}
+
+/// What happened during one step
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    pub observation: Observation,
+    /// Height gained during the step
+    pub reward: f32,
+    pub done: bool,
+    pub death: Option<DeathCause>,
+}
+
+/// An endless run for one agent, stepped TICKS_PER_STEP ticks at a time
+pub struct Env {
+    physics: Physics,
+    sim: Simulation,
+    // ids of the mines in the last observation, in its order
+    observed: Vec<u32>,
+}
+
+impl Env {
+    pub fn new(physics: Physics) -> Self {
+        Self {
+            physics,
+            sim: Simulation::endless(0, physics),
+            observed: Vec::new(),
+        }
+    }
+
+    /// Start a new episode on the layout of `seed`
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.sim = Simulation::endless(seed, self.physics);
+        self.observe()
+    }
+
+    /// Hooking a mine index that wasn't observed does nothing
+    pub fn step(&mut self, action: Action) -> Step {
+        match action {
+            Action::Noop => {}
+            Action::Release => self.sim.hook(0, None),
+            Action::Hook(i) => {
+                if let Some(id) = self.observed.get(i) {
+                    self.sim.hook(0, Some(*id));
+                }
+            }
+        }
+        let before = self.sim.players[0].maxheight;
+        for _ in 0..TICKS_PER_STEP {
+            self.sim.step();
+        }
+        let player = &self.sim.players[0];
+        let reward = player.maxheight - before;
+        let death = player.death;
+        Step {
+            observation: self.observe(),
+            reward,
+            done: self.sim.over() || self.sim.tick >= MAX_TICKS,
+            death,
+        }
+    }
+
+    /// Answer one request line, errors come back as {"error": "..."}
+    pub fn handle(&mut self, line: &str) -> String {
+        let answer = match serde_json::from_str(line) {
+            Ok(Request::Reset { seed }) => serde_json::to_value(self.reset(seed)),
+            Ok(Request::Step(action)) => serde_json::to_value(self.step(action)),
+            Err(e) => Ok(serde_json::json!({ "error": e.to_string() })),
+        };
+        answer
+            .expect("Observations are always valid JSON")
+            .to_string()
+    }
+
+    fn observe(&mut self) -> Observation {
+        let top = self.sim.top();
+        let p = &self.sim.players[0];
+        let mut mines: Vec<_> = self
+            .sim
+            .mines
+            .iter()
+            .filter(|m| on_screen(top, m.position.y))
+            .collect();
+        mines.sort_by(|a, b| {
+            let (da, db) = (
+                a.position.distance(p.position),
+                b.position.distance(p.position),
+            );
+            da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+        });
+        mines.truncate(NEAREST_MINES);
+        self.observed = mines.iter().map(|m| m.id).collect();
+        Observation {
+            x: p.position.x,
+            y: p.position.y,
+            vx: p.velocity.x,
+            vy: p.velocity.y,
+            height: p.maxheight,
+            feather: p.feather,
+            hooked: p.hooked.is_some(),
+            mines: mines
+                .iter()
+                .map(|m| MineView {
+                    dx: m.position.x - p.position.x,
+                    dy: m.position.y - p.position.y,
+                    vx: m.velocity.x,
+                    vy: m.velocity.y,
+                    kind: m.kind,
+                    hooked: p.hooked == Some(m.id),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One line of the JSON-lines protocol
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    /// {"reset": {"seed": 3}}, answered with an Observation
+    Reset { seed: u64 },
+    /// {"step": "noop"} or {"step": {"hook": 0}}, answered with a Step
+    Step(Action),
+}
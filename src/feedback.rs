@@ -0,0 +1,51 @@
+// Sounds and effects, driven by the GameEvents of the gameplay systems so
+// none of them has to know about audio
+
+use bevy::prelude::*;
+use bevy_kira_audio::Audio;
+
+use crate::assets::GameAssets;
+use crate::sim::GameEvent;
+use crate::{Player, PlayerId};
+
+// dead players fade to this
+const DEAD_TINT: Color = Color::rgb(0.3, 0.3, 0.3);
+
+pub fn sound_system(
+    mut events: EventReader<GameEvent>,
+    assets: Res<GameAssets>,
+    audio: Res<Audio>,
+) {
+    for event in events.iter() {
+        match event {
+            GameEvent::Hooked { .. } => audio.play(assets.hook_sound.clone()),
+            GameEvent::WallBounce { .. } => audio.play(assets.wall_sound.clone()),
+            _ => {}
+        }
+    }
+}
+
+/// Grey out players once they die
+pub fn death_effect_system(
+    mut events: EventReader<GameEvent>,
+    mut q_player: Query<(&PlayerId, &mut TextureAtlasSprite), With<Player>>,
+) {
+    for event in events.iter() {
+        if let GameEvent::Died { player, .. } = event {
+            for (id, mut sprite) in q_player.iter_mut() {
+                if id.0 == *player {
+                    sprite.color = DEAD_TINT;
+                }
+            }
+        }
+    }
+}
+
+/// Players come back in their own colors for the next run
+pub fn revive_effect_system(
+    mut q_player: Query<(&PlayerId, &mut TextureAtlasSprite), With<Player>>,
+) {
+    for (id, mut sprite) in q_player.iter_mut() {
+        sprite.color = id.tint();
+    }
+}
@@ -0,0 +1,253 @@
+// Everything shown during a run besides the score: how far up the player is
+// compared to its best, how fast it goes, its combo and its pickups
+
+use bevy::prelude::*;
+
+use crate::assets::GameAssets;
+use crate::physics::{Physics, TIME_STEP};
+use crate::scores::{self, HighScores};
+use crate::style::StyleScore;
+use crate::{ActiveLevel, AppState, GameMode, Player, PlayerId, RACE_HEIGHT};
+
+const BAR_HEIGHT: f32 = 300.;
+// the bar shows this much more than the best, so it can be beaten on screen
+const BEST_HEADROOM: f32 = 1.2;
+// what the bar goes up to before there is any best
+const DEFAULT_SCALE: f32 = 2000.;
+// behind the mines, in front of the parallax layers
+const BEST_LINE_Z: f32 = 0.4;
+
+struct Hud;
+
+struct AltitudeFill;
+
+struct BestMarker;
+
+struct HudText;
+
+struct BestLine;
+
+/// The height the player has to beat in this mode, if any
+fn personal_best(mode: GameMode, high_scores: &HighScores) -> Option<f32> {
+    let best = match mode {
+        GameMode::Endless => Some(high_scores.endless),
+        GameMode::Daily => {
+            let date = scores::date_string(scores::utc_day());
+            high_scores.daily.get(&date).and_then(|r| r.best)
+        }
+        _ => None,
+    };
+    best.filter(|b| *b > 0).map(|b| b as f32)
+}
+
+/// The height the top of the bar stands for
+fn bar_scale(goal: Option<f32>, best: Option<f32>, height: f32) -> f32 {
+    let scale = match (goal, best) {
+        (Some(goal), _) => goal,
+        (None, Some(best)) => best * BEST_HEADROOM,
+        (None, None) => DEFAULT_SCALE,
+    };
+    scale.max(height).max(1.)
+}
+
+pub(crate) fn setup_hud(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    assets: Res<GameAssets>,
+) {
+    let text_style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 24.0,
+        color: Color::rgb(0.5, 0.5, 1.0),
+    };
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text {
+                sections: vec![
+                    // speed, combo, pickups
+                    TextSection {
+                        value: "".to_string(),
+                        style: text_style.clone(),
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            color: Color::rgb(1.0, 0.5, 0.5),
+                            ..text_style.clone()
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            color: Color::rgb(1.0, 0.8, 0.1),
+                            ..text_style
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(50.0),
+                    left: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Hud)
+        .insert(HudText);
+
+    // altitude bar on the right, filling up from the bottom
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(60.0),
+                    right: Val::Px(10.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(12.0), Val::Px(BAR_HEIGHT)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.15, 0.15, 0.15, 0.6).into()),
+            ..Default::default()
+        })
+        .insert(Hud)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            bottom: Val::Px(0.0),
+                            left: Val::Px(0.0),
+                            ..Default::default()
+                        },
+                        size: Size::new(Val::Percent(100.0), Val::Percent(0.0)),
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::rgb(0.5, 0.5, 1.0).into()),
+                    ..Default::default()
+                })
+                .insert(AltitudeFill);
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            bottom: Val::Percent(0.0),
+                            left: Val::Px(-4.0),
+                            ..Default::default()
+                        },
+                        size: Size::new(Val::Px(20.0), Val::Px(2.0)),
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::rgb(1.0, 0.8, 0.1).into()),
+                    ..Default::default()
+                })
+                .insert(BestMarker);
+        });
+
+    // across the board at the previous best
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: materials.add(Color::rgba(1.0, 0.8, 0.1, 0.6).into()),
+            sprite: Sprite::new(Vec2::new(1.0, 2.0)),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(BestLine);
+}
+
+/// Follow the first player, the HUD only shows while playing
+pub(crate) fn hud_system(
+    state: Res<State<AppState>>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    active_level: Res<ActiveLevel>,
+    physics: Res<Physics>,
+    style: Res<StyleScore>,
+    q_player: Query<(&Player, &PlayerId)>,
+    mut q_hud: Query<&mut Style, (With<Hud>, Without<AltitudeFill>, Without<BestMarker>)>,
+    mut q_fill: Query<&mut Style, (With<AltitudeFill>, Without<BestMarker>)>,
+    mut q_marker: Query<&mut Style, (With<BestMarker>, Without<AltitudeFill>)>,
+    mut q_text: Query<&mut Text, With<HudText>>,
+    mut q_line: Query<(&mut Transform, &mut Sprite, &mut Visible), With<BestLine>>,
+) {
+    let player = q_player
+        .iter()
+        .find(|(_, id)| **id == PlayerId(0))
+        .map(|(p, _)| p);
+    let playing = *state.current() == AppState::Game && player.is_some();
+    let display = if playing {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut style in q_hud.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    let best = personal_best(*mode, &high_scores);
+    if let Ok((mut line_t, mut sprite, mut visible)) = q_line.single_mut() {
+        let shown = playing && best.is_some();
+        if visible.is_visible != shown {
+            visible.is_visible = shown;
+        }
+        if let Some(best) = best {
+            line_t.translation = Vec3::new(0., best, BEST_LINE_Z);
+            sprite.size.x = physics.half_width * 2.;
+        }
+    }
+    let player = match player {
+        Some(player) if playing => player,
+        _ => return,
+    };
+
+    let goal = match *mode {
+        GameMode::Level(_) | GameMode::PlayTest => active_level.0.as_ref().map(|l| l.finish),
+        GameMode::TimeAttack(target) => Some(target as f32),
+        GameMode::Race(_) => Some(RACE_HEIGHT),
+        GameMode::Endless | GameMode::Daily => None,
+    };
+    let scale = bar_scale(goal, best, player.maxheight);
+    if let Ok(mut style) = q_fill.single_mut() {
+        style.size.height = Val::Percent(100. * player.maxheight.max(0.) / scale);
+    }
+    if let Ok(mut style) = q_marker.single_mut() {
+        match best {
+            Some(best) => {
+                style.display = Display::Flex;
+                style.position.bottom = Val::Percent(100. * best / scale);
+            }
+            None => style.display = Display::None,
+        }
+    }
+
+    if let Ok(mut text) = q_text.single_mut() {
+        // velocities are per tick
+        let speed = player.velocity.truncate().length() / TIME_STEP;
+        text.sections[0].value = format!("Speed {:.0}", speed);
+        text.sections[1].value = match style.combo {
+            0 | 1 => format!("  Style {}", style.style() as i32),
+            _ => format!(
+                "  Style {}  x{:.2}",
+                style.style() as i32,
+                style.multiplier()
+            ),
+        };
+        text.sections[2].value = if player.feather > 0. {
+            format!("  Feather {:.1}s", player.feather)
+        } else {
+            "".to_string()
+        };
+    }
+}
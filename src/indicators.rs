@@ -0,0 +1,148 @@
+// Arrows along the top of the screen pointing at the mines above it, so
+// players can see what's coming before it scrolls in
+
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+
+use crate::assets::{mine_color, MINE_KINDS};
+use crate::camera::{board_size, visible_half_size, ViewScale};
+use crate::physics::{MineKind, Physics};
+use crate::settings::Settings;
+use crate::{AppState, MainCamera, Mine};
+
+// at most this many arrows, for the closest mines
+const INDICATORS: usize = 6;
+// mines further above the screen than this aren't shown
+const RANGE: f32 = 600.;
+// even the furthest arrow stays this visible
+const MIN_ALPHA: f32 = 0.15;
+// arrows fade in this many steps, one material each
+const ALPHA_STEPS: usize = 8;
+const ARROW_SIZE: f32 = 12.;
+// between the top of the screen and the arrows, and the walls and the arrows
+const MARGIN: f32 = 12.;
+// above everything but the letterbox bars
+const ARROW_Z: f32 = 40.;
+
+pub(crate) struct MineIndicator;
+
+/// The mine's own color, brightened so the strongest channel is full
+fn indicator_color(kind: MineKind, alpha: f32) -> Color {
+    let c = mine_color(kind, false, false);
+    let max = c.r().max(c.g()).max(c.b());
+    Color::rgba(c.r() / max, c.g() / max, c.b() / max, alpha)
+}
+
+/// Fully visible right above the screen, fading out towards RANGE
+fn indicator_step(above: f32) -> usize {
+    ((1. - above / RANGE).max(0.).min(1.) * (ALPHA_STEPS - 1) as f32).round() as usize
+}
+
+fn step_alpha(step: usize) -> f32 {
+    (step as f32 / (ALPHA_STEPS - 1) as f32).max(MIN_ALPHA)
+}
+
+/// ALPHA_STEPS materials per mine kind, in the order of MINE_KINDS
+pub(crate) struct IndicatorMaterials(Vec<Vec<Handle<ColorMaterial>>>);
+
+impl FromWorld for IndicatorMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        IndicatorMaterials(
+            MINE_KINDS
+                .iter()
+                .map(|kind| {
+                    (0..ALPHA_STEPS)
+                        .map(|step| materials.add(indicator_color(*kind, step_alpha(step)).into()))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+impl IndicatorMaterials {
+    fn get(&self, kind: MineKind, step: usize) -> &Handle<ColorMaterial> {
+        let kind = MINE_KINDS.iter().position(|k| *k == kind).unwrap_or(0);
+        &self.0[kind][step]
+    }
+}
+
+pub(crate) fn setup_indicators(mut commands: Commands, materials: Res<IndicatorMaterials>) {
+    for _ in 0..INDICATORS {
+        commands
+            .spawn_bundle(SpriteBundle {
+                // swapped for the mine's kind and distance once shown
+                material: materials.get(MineKind::Normal, 0).clone(),
+                sprite: Sprite::new(Vec2::splat(ARROW_SIZE)),
+                // a square on its corner points up at the edge of the screen
+                transform: Transform::from_rotation(Quat::from_rotation_z(
+                    std::f32::consts::FRAC_PI_4,
+                )),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(MineIndicator);
+    }
+}
+
+/// Put an arrow over each of the closest mines above the screen, only while playing
+pub(crate) fn indicator_system(
+    windows: Res<Windows>,
+    physics: Res<Physics>,
+    settings: Res<Settings>,
+    view: Res<ViewScale>,
+    state: Res<State<AppState>>,
+    materials: Res<IndicatorMaterials>,
+    q_cam: Query<&Transform, (With<MainCamera>, Without<MineIndicator>)>,
+    q_mine: Query<(&Mine, &Transform), Without<MineIndicator>>,
+    mut q_arrow: Query<
+        (&mut Transform, &mut Visible, &mut Handle<ColorMaterial>),
+        With<MineIndicator>,
+    >,
+) {
+    let (cam_t, wnd) = match (q_cam.single(), windows.get_primary()) {
+        (Ok(cam_t), Some(wnd)) => (cam_t, wnd),
+        _ => return,
+    };
+    // arrows keep their size on screen while the camera zooms
+    let zoom = cam_t.scale.x / view.0;
+    let window = Vec2::new(wnd.width(), wnd.height());
+    let half = visible_half_size(window, board_size(&physics), settings.fit_to_window, cam_t);
+    let edge = cam_t.translation.y + half.y;
+
+    let mut above: Vec<(f32, f32, MineKind)> = Vec::new();
+    if *state.current() == AppState::Game {
+        above = q_mine
+            .iter()
+            .map(|(m, m_t)| (m_t.translation.x, m_t.translation.y - edge, m.kind))
+            .filter(|(_, dy, _)| *dy > 0. && *dy < RANGE)
+            .collect();
+        above.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+    }
+
+    let max_x = physics.half_width - MARGIN;
+    for (i, (mut arrow_t, mut visible, mut material)) in q_arrow.iter_mut().enumerate() {
+        let shown = match above.get(i) {
+            Some((x, dy, kind)) => {
+                arrow_t.translation =
+                    Vec3::new(x.max(-max_x).min(max_x), edge - MARGIN * zoom, ARROW_Z);
+                arrow_t.scale = Vec3::new(zoom, zoom, 1.);
+                let faded = materials.get(*kind, indicator_step(*dy));
+                // swap handles instead of recoloring, so nothing is uploaded every frame
+                if *material != *faded {
+                    *material = faded.clone();
+                }
+                true
+            }
+            None => false,
+        };
+        if visible.is_visible != shown {
+            visible.is_visible = shown;
+        }
+    }
+}
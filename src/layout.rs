@@ -0,0 +1,97 @@
+use bevy::math::Vec2;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::physics::{Physics, GAME_BOARD};
+use crate::validator;
+
+// vertical distance between two mines, plus up to 50 random units
+pub const MINE_GAP: f32 = 250.;
+// how many of the latest mines the validator gets to use
+const RECENT_MINES: usize = 4;
+// keep mines spawned this far above the player, so they never pop up on screen
+const MINE_LOOKAHEAD: f32 = 450.;
+
+/// Endless stream of mines that only depends on the seed, so everyone
+/// playing the same seed climbs the same layout
+#[derive(Clone)]
+pub struct MineLayout {
+    pub seed: u64,
+    // StdRng may change between rand versions and platforms, this one won't
+    rng: ChaCha8Rng,
+    recent: Vec<Vec2>,
+    // mines handed out so far, also the id of the next one
+    count: u32,
+}
+
+impl MineLayout {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            recent: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Height of the highest mine handed out so far
+    pub fn top(&self) -> f32 {
+        match self.recent.last() {
+            Some(m) => m.y,
+            // the first mine ends up just above the screen
+            None => GAME_BOARD.3 - MINE_GAP,
+        }
+    }
+
+    /// The player got high enough for another mine
+    pub fn needs_mine(&self, maxheight: f32) -> bool {
+        self.top() - maxheight < MINE_LOOKAHEAD
+    }
+
+    /// Id and position of the next mine
+    pub fn next(&mut self, physics: &Physics) -> (u32, Vec2) {
+        let bottom = self.top() + MINE_GAP;
+        let pos = validator::place_mine(&mut self.rng, bottom, &self.recent, physics)
+            // nothing reachable turned up, straight above the last mine is the
+            // easiest spot at this height
+            .unwrap_or_else(|| {
+                let x = self.recent.last().map_or(0., |m| m.x);
+                Vec2::new(x, bottom)
+            });
+        self.recent.push(pos);
+        if self.recent.len() > RECENT_MINES {
+            self.recent.remove(0);
+        }
+        self.count += 1;
+        (self.count - 1, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_rolls_are_replaced() {
+        let physics = Physics::default();
+        let mut layout = MineLayout::new(0);
+        let mut replaced = 0;
+        for _ in 0..50 {
+            let recent = layout.recent.clone();
+            // what the layout would have placed without the validator
+            let mut rng = layout.rng.clone();
+            let bottom = layout.top() + MINE_GAP;
+            let roll = validator::random_mine_position(&mut rng, bottom, &physics);
+            let (_, pos) = layout.next(&physics);
+            assert!(pos.y >= bottom);
+            if !validator::next_mine_reachable(&recent, roll, &physics) {
+                assert_ne!(pos, roll);
+                replaced += 1;
+            }
+            // either a reachable spot or the fallback above the last mine
+            let fallback = Vec2::new(recent.last().map_or(0., |m| m.x), bottom);
+            assert!(pos == fallback || validator::next_mine_reachable(&recent, pos, &physics));
+        }
+        assert!(replaced > 0);
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::physics::MineKind;
+
+// bundled with the binary so they also work on the web
+const BUNDLED: &[&str] = &[
+    include_str!("../assets/levels/01_first_steps.json"),
+    include_str!("../assets/levels/02_the_chimney.json"),
+    include_str!("../assets/levels/03_feather_fall.json"),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelMine {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub kind: MineKind,
+}
+
+/// Touching one of these kills the player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hazard {
+    // center
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickupKind {
+    /// Instant kick upwards
+    Boost,
+    /// Less gravity for a while
+    Feather,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pickup {
+    pub x: f32,
+    pub y: f32,
+    pub kind: PickupKind,
+}
+
+/// A handcrafted level, stored as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub mines: Vec<LevelMine>,
+    #[serde(default)]
+    pub hazards: Vec<Hazard>,
+    #[serde(default)]
+    pub pickups: Vec<Pickup>,
+    /// Height the player has to reach
+    pub finish: f32,
+    /// Seconds a good run takes
+    pub par_time: f32,
+}
+
+impl Level {
+    pub fn from_json(json: &str) -> Result<Level, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Levels are always valid JSON")
+    }
+}
+
+/// All levels that ship with the game, in menu order
+pub fn bundled() -> Vec<Level> {
+    BUNDLED
+        .iter()
+        .map(|json| Level::from_json(json).expect("Bundled level is broken"))
+        .collect()
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin};
 
 use bevy::{
@@ -6,27 +8,194 @@ use bevy::{
     render::pass::ClearColor,
     sprite::collide_aabb::{collide, Collision},
 };
-use rand::Rng;
 use wasm_bindgen::prelude::*;
 
-const TIME_STEP: f32 = 1.0 / 30.0;
-// left, right, bottom, top
-const GAME_BOARD: (f32, f32, f32, f32) = (-200.0, 200.0, -400.0, 400.0);
-// const GRAVITY_FAC: f32 = 0.09;
-const GRAVITY_FAC: f32 = 0.18;
-// const UPWARD_FAC: f32 = 0.25;
-const UPWARD_FAC: f32 = 0.5;
+mod achievements;
+mod animation;
+mod assets;
+pub mod balance;
+pub mod bot;
+mod camera;
+mod editor;
+pub mod env;
+mod feedback;
+mod hud;
+mod indicators;
+pub mod layout;
+pub mod level;
+mod menu;
+mod parallax;
+mod particles;
+pub mod physics;
+mod preview;
+mod scores;
+mod settings;
+pub mod sim;
+mod storage;
+mod style;
+mod telemetry;
+pub mod validator;
+
+use achievements::{
+    achievement_system, apply_cosmetics, reset_achievement_run, toast_system, AchievementRun,
+    Achievements,
+};
+use animation::{animation_system, Animation};
+use assets::{
+    cleanup_loading, loading_system, setup_loading, GameAssets, LevelMaterials, MineMaterials,
+    PlayerMaterials,
+};
+use bot::{Action, Bot, Observation, Strategy};
+use camera::{
+    camera_system, letterbox_system, reset_camera, screen_to_world, setup_letterbox,
+    view_scale_system, CameraRig, ViewScale,
+};
+use editor::{
+    cleanup_editor, editor_input_system, editor_render_system, setup_editor, EditorState,
+};
+use feedback::{death_effect_system, revive_effect_system, sound_system};
+use hud::{hud_system, setup_hud};
+use indicators::{indicator_system, setup_indicators, IndicatorMaterials};
+use layout::MineLayout;
+use level::{Level, PickupKind};
+use menu::{
+    cleanup_menu, cosmetics_label_system, menu, settings_label_system, setup_achievements,
+    setup_menu, setup_modes, setup_results, setup_settings, ButtonMaterials,
+};
+use parallax::{parallax_system, setup_parallax, ParallaxMaterials};
+use particles::{particle_system, setup_particles, ParticleConfig, ParticleMaterials};
+use physics::{
+    on_screen, start_velocity, too_low, MineKind, Physics, GAME_BOARD, PICKUP_SIZE, PLAYER_SIZE,
+    START_OFFSET, TIME_STEP,
+};
+use preview::{preview_system, setup_preview};
+use rand::Rng;
+use scores::{HighScores, TimeAttackRecord};
+use settings::Settings;
+use sim::{DeathCause, GameEvent, HookEvent, Playback, Replay, SimMine, SimPlayer, Simulation};
+use style::{reset_style, style_system, StyleScore};
+use telemetry::{telemetry_system, Telemetry};
+
+// heights a time attack can be played to
+const TIME_ATTACK_TARGETS: [u32; 2] = [2000, 5000];
+// a split time is taken every this many units
+const SPLIT_HEIGHT: f32 = 1000.;
+// first one up here wins a race
+const RACE_HEIGHT: f32 = 5000.;
+// races are played on a wider board
+const RACE_HALF_WIDTH: f32 = 300.;
+// racers start this far left and right of the center
+const RACE_START_X: f32 = 80.;
+const PLAYER_TINTS: [Color; 2] = [Color::WHITE, Color::rgb(1.0, 0.6, 0.3)];
+
+// order of the fixed timestep systems, the headless simulation does the same
+const INPUT: &str = "input";
+const PULL: &str = "pull";
+const FORCES: &str = "forces";
+const COLLIDE: &str = "collide";
+const MOVE: &str = "move";
+// everything that reacts to the movement, the tick counter goes up after it
+const POST: &str = "post";
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
 enum AppState {
     WarmUp,
     Menu,
+    Modes,
     Game,
+    Results,
+    Editor,
+    Settings,
+    Achievements,
+}
+
+/// What kind of run is being played
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum GameMode {
+    #[default]
+    Endless,
+    /// Endless, but everyone gets the same layout on the same day
+    Daily,
+    /// Index into the bundled levels
+    Level(usize),
+    /// The level in the editor
+    PlayTest,
+    /// Reach the target height as fast as possible
+    TimeAttack(u32),
+    /// Two players on one screen, first to RACE_HEIGHT wins
+    Race(Rival),
+}
+
+/// Who plays the second player of a race
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rival {
+    /// Another human on the keyboard
+    Keyboard,
+    Bot(Strategy),
+}
+
+/// The bundled levels
+struct Levels(Vec<Level>);
+
+impl Default for Levels {
+    fn default() -> Self {
+        Levels(level::bundled())
+    }
+}
+
+/// The level being played, if any
+#[derive(Default)]
+struct ActiveLevel(Option<Level>);
+
+/// Date of the daily challenge, if this run is the attempt that counts
+#[derive(Default)]
+struct CountedDaily(Option<String>);
+
+/// Seconds since the run started
+#[derive(Default)]
+struct RunTimer {
+    elapsed: f32,
+    ticks: u32,
+    /// Time whenever another SPLIT_HEIGHT was reached
+    splits: Vec<f32>,
+}
+
+/// Clicks, touches and key presses since the last tick, applied at the start of the next one
+#[derive(Default)]
+struct HookInput {
+    // player and the mine it hooks, None is a release
+    pending: Vec<(Entity, Option<u32>)>,
+}
+
+/// Hooks of the current run, to save as a replay
+#[derive(Default)]
+struct Recorder(Replay);
+
+impl Recorder {
+    fn replay(&self, time: f32) -> Replay {
+        Replay {
+            time,
+            ..self.0.clone()
+        }
+    }
+}
+
+/// The personal best playing along, if there is one
+#[derive(Default)]
+struct Ghost(Option<Playback>);
+
+/// How the last run went, for the results screen
+struct RunResult {
+    title: String,
+    lines: Vec<String>,
 }
+
 struct Mine {
+    /// Stable within a run, replays refer to mines by it
+    id: u32,
     selected: bool,
-    hooked: bool,
     velocity: Vec3,
+    kind: MineKind,
 }
 
 // Just a marker for the bg
@@ -35,14 +204,29 @@ struct MainCamera;
 //Marker for the highscoretext
 struct ScoreText;
 
-struct Line;
+// The rope of a player
+struct Line(Entity);
+
+// The ghost and its rope
+struct GhostPlayer;
+struct GhostLine;
+
+// Everything a level spawns besides its mines
+struct LevelEntity;
+// The second player and its rope
+struct RaceEntity;
+struct HazardArea {
+    size: Vec2,
+}
+struct PickupItem(PickupKind);
 
 impl Default for Mine {
     fn default() -> Self {
         Self {
+            id: 0,
             selected: false,
-            hooked: false,
             velocity: Vec3::default(),
+            kind: MineKind::Normal,
         }
     }
 }
@@ -51,7 +235,43 @@ impl Default for Mine {
 struct Player {
     velocity: Vec3,
     maxheight: f32,
-    dead: bool,
+    death: Option<DeathCause>,
+    // seconds of feather left
+    feather: f32,
+    /// Id of the mine on the other end of the rope
+    hooked: Option<u32>,
+}
+
+impl Player {
+    fn dead(&self) -> bool {
+        self.death.is_some()
+    }
+}
+
+/// Which input device steers a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Controls {
+    /// Mouse or touch, hooks the mine under the cursor
+    Pointer,
+    /// Hold W or Up to hook the closest mine above
+    Keyboard,
+    /// Played by the Bot component
+    Bot,
+}
+
+/// Tells players apart, also the index into PLAYER_TINTS and of replay events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct PlayerId(u8);
+
+impl PlayerId {
+    /// What players see, counting from 1
+    fn number(&self) -> u8 {
+        self.0 + 1
+    }
+
+    fn tint(&self) -> Color {
+        PLAYER_TINTS[self.0 as usize % PLAYER_TINTS.len()]
+    }
 }
 
 // impl Default for Player {
@@ -76,46 +296,164 @@ pub fn run() {
     app.add_plugins(DefaultPlugins)
         .add_plugin(AudioPlugin)
         .insert_resource(ClearColor(Color::rgb(0.9, 0.9, 0.9)))
+        .init_resource::<GameAssets>()
+        .init_resource::<MineMaterials>()
+        .init_resource::<LevelMaterials>()
+        .init_resource::<PlayerMaterials>()
+        .init_resource::<ParticleConfig>()
+        .init_resource::<ParticleMaterials>()
+        .init_resource::<ParallaxMaterials>()
+        .init_resource::<IndicatorMaterials>()
         .init_resource::<ButtonMaterials>()
+        .init_resource::<Physics>()
+        .init_resource::<GameMode>()
+        .init_resource::<Levels>()
+        .init_resource::<RunTimer>()
+        .init_resource::<ActiveLevel>()
+        .init_resource::<CountedDaily>()
+        .init_resource::<HookInput>()
+        .init_resource::<Recorder>()
+        .init_resource::<Ghost>()
+        .init_resource::<Telemetry>()
+        .add_event::<GameEvent>()
+        .insert_resource(MineLayout::new(0))
+        .insert_resource(HighScores::load())
+        .insert_resource(Settings::load())
+        .insert_resource(Achievements::load())
+        .init_resource::<AchievementRun>()
+        .init_resource::<CameraRig>()
+        .init_resource::<StyleScore>()
+        .init_resource::<ViewScale>()
+        .init_resource::<EditorState>()
         .add_state(AppState::WarmUp)
         .add_system_set(
             SystemSet::on_enter(AppState::WarmUp)
                 .with_system(setup.system())
+                .with_system(setup_loading.system())
+                .with_system(setup_particles.system())
+                .with_system(setup_letterbox.system())
+                .with_system(setup_parallax.system())
+                .with_system(setup_preview.system())
+                .with_system(setup_indicators.system())
+                .with_system(setup_hud.system()),
+        )
+        .add_system_set(SystemSet::on_update(AppState::WarmUp).with_system(loading_system.system()))
+        .add_system_set(
+            SystemSet::on_exit(AppState::WarmUp)
+                .with_system(cleanup_loading.system())
                 .with_system(start_music.system()),
         )
-        .add_system_set(SystemSet::on_enter(AppState::Game).with_system(init_game.system()))
+        .add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(init_game.system().label("init_game"))
+                .with_system(init_race.system().after("init_game"))
+                .with_system(revive_effect_system.system().label("revive"))
+                .with_system(apply_cosmetics.system().after("revive"))
+                .with_system(reset_camera.system())
+                .with_system(reset_style.system())
+                .with_system(reset_achievement_run.system()),
+        )
         .add_system_set(
             SystemSet::on_update(AppState::Game)
                 .with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
-                .with_system(velocity_towards_mine_system.system())
-                .with_system(velocity_towards_player_system.system())
-                .with_system(gravity_system.system())
-                .with_system(wall_collision_system.system())
-                .with_system(bg_system.system())
-                .with_system(spawn_new_mine_system.system())
+                .with_system(bot_system.system().before(INPUT))
+                .with_system(apply_hook_input_system.system().label(INPUT))
+                .with_system(
+                    velocity_towards_mine_system
+                        .system()
+                        .label(PULL)
+                        .after(INPUT),
+                )
+                .with_system(
+                    velocity_towards_player_system
+                        .system()
+                        .label(FORCES)
+                        .after(INPUT),
+                )
+                // floats don't add up the same in any order, so gravity comes last
+                .with_system(gravity_system.system().label(FORCES).after(PULL))
+                .with_system(wall_collision_system.system().label(COLLIDE).after(FORCES))
+                .with_system(player_movement_system.system().label(MOVE).after(COLLIDE))
+                .with_system(mine_movement_system.system().label(MOVE).after(COLLIDE))
+                .with_system(player_too_low_system.system().label(POST).after(MOVE))
+                .with_system(hazard_system.system().label(POST).after(MOVE))
+                .with_system(pickup_system.system().label(POST).after(MOVE))
+                .with_system(spawn_new_mine_system.system().label(POST).after(MOVE))
+                .with_system(clean_old_mines_system.system().label(POST).after(MOVE))
+                .with_system(style_system.system().label("style").after(POST))
+                .with_system(achievement_system.system().after("style"))
+                .with_system(telemetry_system.system().label("telemetry").after(POST))
+                .with_system(run_timer_system.system().after("telemetry"))
+                .with_system(ghost_step_system.system())
                 // -.with_system(play_hooked_system.system())
-                .with_system(clean_old_mines_system.system()),
+                .with_system(bg_system.system()),
         )
         .add_system_set(
             SystemSet::on_update(AppState::Game)
-                .with_system(player_movement_system.system())
-                .with_system(mine_movement_system.system())
                 .with_system(mine_selector_system.system())
                 .with_system(mine_highlighter_system.system())
                 .with_system(draw_line_system.system())
-                .with_system(move_camera_system.system())
+                .with_system(animation_system.system())
+                .with_system(ghost_render_system.system())
+                .with_system(camera_system.system().label("camera").after("view_scale"))
                 .with_system(is_player_dead_system.system())
-                .with_system(player_too_low_system.system())
-                .with_system(mine_hook_system.system()),
+                .with_system(finish_line_system.system())
+                .with_system(time_attack_system.system())
+                .with_system(race_system.system())
+                .with_system(mine_hook_system.system())
+                .with_system(sound_system.system())
+                .with_system(death_effect_system.system()),
         )
         .add_system_set(
             SystemSet::on_exit(AppState::Game)
                 .with_system(end_game_system.system())
-                .with_system(setup_menu.system()),
+                .with_system(reset_camera.system()),
         )
+        .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(setup_menu.system()))
         .add_system_set(SystemSet::on_update(AppState::Menu).with_system(menu.system()))
         .add_system_set(SystemSet::on_exit(AppState::Menu).with_system(cleanup_menu.system()))
+        .add_system_set(SystemSet::on_enter(AppState::Modes).with_system(setup_modes.system()))
+        .add_system_set(SystemSet::on_update(AppState::Modes).with_system(menu.system()))
+        .add_system_set(SystemSet::on_exit(AppState::Modes).with_system(cleanup_menu.system()))
+        .add_system_set(SystemSet::on_enter(AppState::Results).with_system(setup_results.system()))
+        .add_system_set(SystemSet::on_update(AppState::Results).with_system(menu.system()))
+        .add_system_set(SystemSet::on_exit(AppState::Results).with_system(cleanup_menu.system()))
+        .add_system_set(
+            SystemSet::on_enter(AppState::Settings).with_system(setup_settings.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Settings)
+                .with_system(menu.system())
+                .with_system(settings_label_system.system()),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Settings).with_system(cleanup_menu.system()))
+        .add_system_set(
+            SystemSet::on_enter(AppState::Achievements).with_system(setup_achievements.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Achievements)
+                .with_system(menu.system())
+                .with_system(cosmetics_label_system.system()),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::Achievements).with_system(cleanup_menu.system()),
+        )
+        .add_system_set(SystemSet::on_enter(AppState::Editor).with_system(setup_editor.system()))
+        .add_system_set(
+            SystemSet::on_update(AppState::Editor)
+                .with_system(editor_input_system.system())
+                .with_system(editor_render_system.system()),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Editor).with_system(cleanup_editor.system()))
         .add_system(scoreboard_system.system())
+        .add_system(particle_system.system())
+        .add_system(view_scale_system.system().label("view_scale"))
+        .add_system(letterbox_system.system().after("camera"))
+        .add_system(parallax_system.system().after("camera"))
+        .add_system(preview_system.system())
+        .add_system(indicator_system.system().after("camera"))
+        .add_system(hud_system.system())
+        .add_system(toast_system.system())
         .add_system(bevy::input::system::exit_on_esc_system.system());
     // app.add_state(AppState::End);
     // when building for Web, use WebGL2 rendering
@@ -127,15 +465,15 @@ pub fn run() {
     app.run();
 }
 
-fn start_music(asset_server: Res<AssetServer>, audio: Res<Audio>) {
-    audio.play_looped(asset_server.load("music.ogg"));
+fn start_music(assets: Res<GameAssets>, audio: Res<Audio>) {
+    audio.play_looped(assets.music.clone());
 }
 
 fn setup(
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
-    mut state: ResMut<State<AppState>>,
+    player_materials: Res<PlayerMaterials>,
+    assets: Res<GameAssets>,
 ) {
     // Add the game's entities to our world
 
@@ -150,29 +488,44 @@ fn setup(
         .spawn_bundle(SpriteBundle {
             material: materials.add(Color::rgb(0.5, 0.5, 1.0).into()),
             transform: Transform::from_xyz(0.0, 0.0, 0.0),
-            sprite: Sprite::new(Vec2::new(GAME_BOARD.1 * 2. + 32., 1000.0)),
+            // tall enough for the camera to zoom out and look ahead
+            sprite: Sprite::new(Vec2::new(GAME_BOARD.1 * 2. + 32., 1600.0)),
             ..Default::default()
         })
         .insert(Background);
 
-    // line
+    // player and line
+    spawn_player(
+        &mut commands,
+        &player_materials,
+        &assets,
+        PlayerId(0),
+        Controls::Pointer,
+    );
+
+    // ghost of the personal best, only shown while one is playing along
     commands
         .spawn_bundle(SpriteBundle {
-            material: materials.add(Color::rgb(1.0, 1.0, 1.0).into()),
-            transform: Transform::from_xyz(0.0, 0.0, 0.9),
-            sprite: Sprite::new(Vec2::new(1.0, 2.0)),
+            material: player_materials.ghost.clone(),
+            sprite: Sprite::new(Vec2::splat(PLAYER_SIZE)),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
             ..Default::default()
         })
-        .insert(Line);
-
-    // player
+        .insert(GhostPlayer);
     commands
         .spawn_bundle(SpriteBundle {
-            material: materials.add(asset_server.load("player.png").into()),
-            sprite: Sprite::new(Vec2::new(32.0, 32.0)),
+            material: player_materials.ghost_line.clone(),
+            sprite: Sprite::new(Vec2::new(1.0, 2.0)),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
             ..Default::default()
         })
-        .insert(Player::default());
+        .insert(GhostLine);
 
     // scoreboard
     commands
@@ -180,9 +533,9 @@ fn setup(
             text: Text {
                 sections: vec![
                     TextSection {
-                        value: "Score: ".to_string(),
+                        value: "Height: ".to_string(),
                         style: TextStyle {
-                            font: asset_server.load("vcr.ttf"),
+                            font: assets.font.clone(),
                             font_size: 40.0,
                             color: Color::rgb(0.5, 0.5, 1.0),
                         },
@@ -190,7 +543,7 @@ fn setup(
                     TextSection {
                         value: "".to_string(),
                         style: TextStyle {
-                            font: asset_server.load("vcr.ttf"),
+                            font: assets.font.clone(),
                             font_size: 40.0,
                             color: Color::rgb(1.0, 0.5, 0.5),
                         },
@@ -210,9 +563,45 @@ fn setup(
             ..Default::default()
         })
         .insert(ScoreText);
+}
 
-    // proceed to game
-    state.set(AppState::Game).unwrap();
+/// A player and its rope, returns both
+fn spawn_player(
+    commands: &mut Commands,
+    materials: &PlayerMaterials,
+    assets: &GameAssets,
+    id: PlayerId,
+    controls: Controls,
+) -> (Entity, Entity) {
+    let player = commands
+        .spawn_bundle(SpriteSheetBundle {
+            texture_atlas: assets.player_atlas.clone(),
+            sprite: TextureAtlasSprite {
+                color: id.tint(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Player::default())
+        .insert(Animation::default())
+        .insert(id)
+        .insert(controls)
+        .id();
+    let line = commands
+        .spawn_bundle(SpriteBundle {
+            material: materials.line(id),
+            transform: Transform::from_xyz(0.0, 0.0, 0.9),
+            sprite: Sprite::new(Vec2::new(1.0, 2.0)),
+            ..Default::default()
+        })
+        .insert(Line(player))
+        .id();
+    (player, line)
+}
+
+/// Highest any player got, the camera follows it
+fn top_height(maxheights: impl Iterator<Item = f32>) -> Option<f32> {
+    maxheights.reduce(f32::max)
 }
 
 /// Use the mouse to select a mine
@@ -226,25 +615,14 @@ fn mine_selector_system(
     // get the primary window
     let wnd = wnds.get_primary().unwrap();
 
-    // check if the cursor is in the primary window
-    if let Some(pos) = wnd.cursor_position() {
-        // get the size of the window
-        let size = Vec2::new(wnd.width() as f32, wnd.height() as f32);
-
-        // the default orthographic projection is in pixels from the center;
-        // just undo the translation
-        let p = pos - size / 2.0;
-
-        // assuming there is exactly one main camera entity, so this is OK
-        let camera_transform = q_camera.single().expect("Need exactly one camera");
-
-        // apply the camera transform
-        let pos_wld = camera_transform.compute_matrix() * p.extend(0.0).extend(1.0);
+    // assuming there is exactly one main camera entity, so this is OK
+    let camera_transform = q_camera.single().expect("Need exactly one camera");
 
+    // check if the cursor is in the primary window
+    if let Some(pos_wld) = cursor_to_world(wnd, camera_transform) {
         for (t_mine, mut mine) in q_mine.iter_mut() {
             let a = Vec2::new(t_mine.translation.x, t_mine.translation.y);
-            let b = Vec2::new(pos_wld.x, pos_wld.y);
-            let d = dist(a, b);
+            let d = dist(a, pos_wld);
 
             if d < 30. {
                 mine.selected = true
@@ -255,136 +633,480 @@ fn mine_selector_system(
     }
 }
 
+/// Where the cursor points in the world, if it is in the window
+fn cursor_to_world(wnd: &Window, camera_transform: &Transform) -> Option<Vec2> {
+    let pos = wnd.cursor_position()?;
+    let size = Vec2::new(wnd.width() as f32, wnd.height() as f32);
+    Some(screen_to_world(pos, size, camera_transform))
+}
+
 /// Highlight mine under cursor and if hooked
 fn mine_highlighter_system(
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut q_mine: Query<(&Handle<ColorMaterial>, &Mine), With<Sprite>>,
+    mine_materials: Res<MineMaterials>,
+    q_player: Query<&Player>,
+    mut q_mine: Query<(&mut Handle<ColorMaterial>, &Mine), With<Sprite>>,
 ) {
-    for (handle, mine) in &mut q_mine.iter_mut() {
-        if let Some(mat) = materials.get_mut(handle) {
-            if mine.selected {
-                mat.color.set_g(4.);
-            } else {
-                mat.color.set_g(1.);
-            }
-            if mine.hooked {
-                mat.color.set_b(4.);
-            } else {
-                mat.color.set_b(1.);
-            }
+    let hooked: Vec<u32> = q_player.iter().filter_map(|p| p.hooked).collect();
+    for (mut handle, mine) in q_mine.iter_mut() {
+        let material = mine_materials.get(mine.kind, mine.selected, hooked.contains(&mine.id));
+        if *handle != material {
+            *handle = material;
         }
     }
 }
 
-/// Mark a mine hooked
-
+/// Hook on click, touch or key press, release on let go
 fn mine_hook_system(
     btns: Res<Input<MouseButton>>,
-    mut q_mine: Query<&mut Mine>,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    keys: Res<Input<KeyCode>>,
+    q_player: Query<(Entity, &Player, &Transform, &Controls)>,
+    q_mine: Query<(&Mine, &Transform)>,
+    mut input: ResMut<HookInput>,
     touches: Res<Touches>, // mut evr_touch: Local<EventReader<TouchInput>>,
 ) {
+    let top = top_height(q_player.iter().map(|(_, p, _, _)| p.maxheight)).unwrap_or(0.);
+    for (entity, _, p_t, controls) in q_player.iter() {
+        let (pressed, released, target) = match controls {
+            Controls::Pointer => {
+                let (pressed, released) = pointer_input(&btns, &touches);
+                let target = q_mine.iter().find(|(m, _)| m.selected).map(|(m, _)| m.id);
+                (pressed, released, target)
+            }
+            Controls::Keyboard => {
+                let hook_keys = [KeyCode::W, KeyCode::Up];
+                let mines = q_mine.iter().map(|(m, m_t)| (m.id, m_t.translation));
+                (
+                    hook_keys.iter().any(|k| keys.just_pressed(*k)),
+                    hook_keys.iter().any(|k| keys.just_released(*k)),
+                    closest_mine_above(p_t.translation, top, mines),
+                )
+            }
+            Controls::Bot => continue,
+        };
+        if pressed {
+            input.pending.push((entity, target));
+        }
+        if released {
+            input.pending.push((entity, None));
+        }
+    }
+}
+
+/// Bots hook and release through the same input as humans, once per tick
+fn bot_system(
+    physics: Res<Physics>,
+    mut input: ResMut<HookInput>,
+    mut q_bot: Query<(Entity, &mut Bot, &Player, &Transform)>,
+    q_player: Query<&Player>,
+    q_mine: Query<(&Mine, &Transform)>,
+) {
+    let top = top_height(q_player.iter().map(|p| p.maxheight)).unwrap_or(0.);
+    let mines: Vec<SimMine> = q_mine
+        .iter()
+        .map(|(m, m_t)| SimMine {
+            id: m.id,
+            position: m_t.translation,
+            velocity: m.velocity,
+            kind: m.kind,
+        })
+        .collect();
+    for (entity, mut bot, player, p_t) in q_bot.iter_mut() {
+        if player.dead() {
+            continue;
+        }
+        let obs = Observation {
+            player: SimPlayer {
+                position: p_t.translation,
+                velocity: player.velocity,
+                maxheight: player.maxheight,
+                death: None,
+                feather: player.feather,
+                hooked: player.hooked,
+            },
+            top,
+            mines: mines.clone(),
+        };
+        match bot.act(&obs, &physics) {
+            Action::Wait => {}
+            Action::Hook(id) => input.pending.push((entity, Some(id))),
+            Action::Release => input.pending.push((entity, None)),
+        }
+    }
+}
+
+/// The mine a keyboard player grabs: the closest one above it on screen
+fn closest_mine_above(
+    player: Vec3,
+    top: f32,
+    mines: impl Iterator<Item = (u32, Vec3)>,
+) -> Option<u32> {
+    mines
+        .filter(|(_, pos)| pos.y > player.y && on_screen(top, pos.y))
+        .min_by(|a, b| {
+            let (da, db) = (a.1.distance(player), b.1.distance(player));
+            da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+        })
+        .map(|(id, _)| id)
+}
+
+/// Whether the mouse or a finger went down or up this frame
+fn pointer_input(btns: &Input<MouseButton>, touches: &Touches) -> (bool, bool) {
     // touch
     let mut touch_active = false;
+    let mut pressed = false;
+    let mut released = false;
 
-    for touch in touches.iter_just_pressed() {
+    for _touch in touches.iter_just_pressed() {
         dbg!("Touch pressed");
         touch_active = true;
-        for mut m in &mut q_mine.iter_mut() {
-            if m.selected {
-                m.hooked = true;
-                audio.play(asset_server.load("sfx100v2_air_02.ogg"));
-            } else {
-                m.hooked = false;
-            }
-        }
+        pressed = true;
     }
 
-    for touch in touches.iter_just_released() {
+    for _touch in touches.iter_just_released() {
         dbg!("Touch release");
         touch_active = true;
-        for mut m in &mut q_mine.iter_mut() {
-            m.hooked = false;
-        }
+        released = true;
     }
 
     if !touch_active {
         if btns.just_pressed(MouseButton::Left) {
-            dbg!("Mouse pressed");
             // a left click just happened
-            for mut m in &mut q_mine.iter_mut() {
-                if m.selected {
-                    m.hooked = true;
-                    audio.play(asset_server.load("sfx100v2_air_02.ogg"));
-                } else {
-                    m.hooked = false;
-                }
-            }
+            dbg!("Mouse pressed");
+            pressed = true;
         }
         if btns.just_released(MouseButton::Left) {
             // deselect
             dbg!("Mouse released");
+            released = true;
+        }
+    }
+
+    (pressed, released)
+}
 
-            for mut m in &mut q_mine.iter_mut() {
-                m.hooked = false;
+/// Apply the hook input at the start of a tick and record it for the replay
+fn apply_hook_input_system(
+    mut input: ResMut<HookInput>,
+    timer: Res<RunTimer>,
+    mut recorder: ResMut<Recorder>,
+    mut events: EventWriter<GameEvent>,
+    mut q_player: Query<(&mut Player, &PlayerId)>,
+    q_mine: Query<(&Mine, &Transform)>,
+) {
+    for (entity, target) in input.pending.drain(..) {
+        if let Ok((mut player, id)) = q_player.get_mut(entity) {
+            // a mine that is gone by now can't be hooked anymore
+            let mine = target.and_then(|target| q_mine.iter().find(|(m, _)| m.id == target));
+            match mine {
+                Some((m, m_t)) => events.send(GameEvent::Hooked {
+                    player: id.0,
+                    mine: m.id,
+                    x: m_t.translation.x,
+                    y: m_t.translation.y,
+                }),
+                None if player.hooked.is_some() => {
+                    events.send(GameEvent::Released { player: id.0 })
+                }
+                None => {}
             }
+            player.hooked = mine.map(|(m, _)| m.id);
+            recorder.0.events.push(HookEvent {
+                tick: timer.ticks,
+                player: id.0,
+                mine: target,
+            });
         }
     }
 }
 
 // Make sure there are enough things to grab
 fn spawn_new_mine_system(
-    q_mine: Query<&Transform, With<Mine>>,
     q_player: Query<&Player>,
     mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
+    mine_materials: Res<MineMaterials>,
+    physics: Res<Physics>,
+    active_level: Res<ActiveLevel>,
+    mut layout: ResMut<MineLayout>,
+    mut events: EventWriter<GameEvent>,
 ) {
-    if let Ok(p) = q_player.single() {
-        let mut highest_mine: f32 = 0.0;
-        for mine_t in q_mine.iter() {
-            highest_mine = highest_mine.max(mine_t.translation.y);
-        }
-
-        // if
-        if highest_mine - p.maxheight < 150. {
-            let mut rng = rand::thread_rng();
-
-            commands
-                .spawn_bundle(SpriteBundle {
-                    material: materials.add(asset_server.load("mine.png").into()),
-                    sprite: Sprite::new(Vec2::new(32.0, 32.0)),
-                    transform: Transform::from_xyz(
-                        rng.gen_range(GAME_BOARD.0..GAME_BOARD.1),
-                        rng.gen_range(p.maxheight + GAME_BOARD.3..p.maxheight + 450.),
-                        1.0,
-                    ),
-                    ..Default::default()
-                })
-                .insert(Mine::default());
+    // levels bring their own mines
+    if active_level.0.is_some() {
+        return;
+    }
+    // every other mode climbs a seeded layout, Endless too, on a fresh random
+    // seed each run, so its runs can be replayed and raced like the others
+    if let Some(top) = top_height(q_player.iter().map(|p| p.maxheight)) {
+        if layout.needs_mine(top) {
+            let (id, pos) = layout.next(&physics);
+            spawn_mine(&mut commands, &mine_materials, id, pos, MineKind::Normal);
+            events.send(GameEvent::MineSpawned {
+                mine: id,
+                x: pos.x,
+                y: pos.y,
+            });
         }
     }
 }
 
-/// Follow the player upward
-fn move_camera_system(
+fn spawn_mine(
+    commands: &mut Commands,
+    mine_materials: &MineMaterials,
+    id: u32,
+    pos: Vec2,
+    kind: MineKind,
+) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: mine_materials.get(kind, false, false),
+            sprite: Sprite::new(Vec2::new(32.0, 32.0)),
+            transform: Transform::from_xyz(pos.x, pos.y, 1.0),
+            ..Default::default()
+        })
+        .insert(Mine {
+            id,
+            kind,
+            ..Default::default()
+        });
+}
+
+/// Put the mines, hazards, pickups and the finish line of a level into the world
+fn spawn_level(
+    commands: &mut Commands,
+    materials: &LevelMaterials,
+    mine_materials: &MineMaterials,
+    level: &Level,
+) {
+    for (i, mine) in level.mines.iter().enumerate() {
+        spawn_mine(
+            commands,
+            mine_materials,
+            i as u32,
+            Vec2::new(mine.x, mine.y),
+            mine.kind,
+        );
+    }
+    for hazard in level.hazards.iter() {
+        let size = Vec2::new(hazard.width, hazard.height);
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.hazard.clone(),
+                sprite: Sprite::new(size),
+                transform: Transform::from_xyz(hazard.x, hazard.y, 0.5),
+                ..Default::default()
+            })
+            .insert(HazardArea { size })
+            .insert(LevelEntity);
+    }
+    for pickup in level.pickups.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.pickup(pickup.kind),
+                sprite: Sprite::new(Vec2::splat(PICKUP_SIZE)),
+                transform: Transform::from_xyz(pickup.x, pickup.y, 0.5),
+                ..Default::default()
+            })
+            .insert(PickupItem(pickup.kind))
+            .insert(LevelEntity);
+    }
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: materials.finish.clone(),
+            sprite: Sprite::new(Vec2::new(GAME_BOARD.1 * 2. + 32., 4.0)),
+            transform: Transform::from_xyz(0.0, level.finish, 0.5),
+            ..Default::default()
+        })
+        .insert(LevelEntity);
+}
+
+/// The dead drop their rope, returns true if the player only died now
+fn kill(player: &mut Player, cause: DeathCause) -> bool {
+    player.hooked = None;
+    if player.dead() {
+        return false;
+    }
+    player.death = Some(cause);
+    true
+}
+
+fn died(id: &PlayerId, cause: DeathCause, position: Vec3) -> GameEvent {
+    GameEvent::Died {
+        player: id.0,
+        cause,
+        x: position.x,
+        y: position.y,
+    }
+}
+
+/// Kill players that touch a hazard
+fn hazard_system(
+    mut events: EventWriter<GameEvent>,
+    mut q_player: Query<(&mut Player, &PlayerId, &Transform)>,
+    q_hazard: Query<(&Transform, &HazardArea)>,
+) {
+    for (mut p, id, p_t) in q_player.iter_mut() {
+        for (h_t, hazard) in q_hazard.iter() {
+            if physics::overlaps(
+                p_t.translation,
+                Vec2::splat(PLAYER_SIZE),
+                h_t.translation,
+                hazard.size,
+            ) {
+                if kill(&mut p, DeathCause::Hazard) {
+                    events.send(died(id, DeathCause::Hazard, p_t.translation));
+                }
+            }
+        }
+    }
+}
+
+/// Collect pickups players fly through, each one only once
+fn pickup_system(
+    mut commands: Commands,
+    mut q_player: Query<(&mut Player, &Transform)>,
+    q_pickup: Query<(Entity, &Transform, &PickupItem)>,
+) {
+    for (entity, pu_t, pickup) in q_pickup.iter() {
+        for (mut p, p_t) in q_player.iter_mut() {
+            if physics::overlaps(
+                p_t.translation,
+                Vec2::splat(PLAYER_SIZE),
+                pu_t.translation,
+                Vec2::splat(PICKUP_SIZE),
+            ) {
+                let p = &mut *p;
+                physics::collect(pickup.0, &mut p.velocity, &mut p.feather);
+                commands.entity(entity).despawn();
+                break;
+            }
+        }
+    }
+}
+
+/// Reaching the finish line ends a level
+fn finish_line_system(
+    mut commands: Commands,
+    mut app_state: ResMut<State<AppState>>,
+    mode: Res<GameMode>,
+    active_level: Res<ActiveLevel>,
+    timer: Res<RunTimer>,
+    recorder: Res<Recorder>,
+    q_player: Query<(&Player, &Transform)>,
+) {
+    if let Some(level) = &active_level.0 {
+        // the first living player across ends it
+        let across = q_player
+            .iter()
+            .any(|(p, p_t)| !p.dead() && p_t.translation.y >= level.finish);
+        if across {
+            if *mode == GameMode::PlayTest {
+                end_run(&mut app_state, AppState::Editor);
+                return;
+            }
+            if !end_run(&mut app_state, AppState::Results) {
+                return;
+            }
+            let verdict = if timer.elapsed <= level.par_time {
+                "Under par!"
+            } else {
+                "Over par"
+            };
+            let key = level_replay_key(&level.name);
+            if load_replay(&key).map_or(true, |best| timer.elapsed < best.time) {
+                save_replay(&key, &recorder.replay(timer.elapsed));
+            }
+            commands.insert_resource(RunResult {
+                title: format!("{} finished", level.name),
+                lines: vec![
+                    format!("Time: {:.1}s  Par: {:.1}s", timer.elapsed, level.par_time),
+                    verdict.to_string(),
+                ],
+            });
+        }
+    }
+}
+
+/// Leave the run for `next`, false if another system already ended it this
+/// tick, like crossing the finish line while dying
+fn end_run(app_state: &mut State<AppState>, next: AppState) -> bool {
+    app_state.set(next).is_ok()
+}
+
+fn run_timer_system(mut timer: ResMut<RunTimer>) {
+    timer.elapsed += TIME_STEP;
+    timer.ticks += 1;
+}
+
+/// Take split times and end time attack runs at the target height
+fn time_attack_system(
+    mut commands: Commands,
+    mut app_state: ResMut<State<AppState>>,
+    mode: Res<GameMode>,
+    mut timer: ResMut<RunTimer>,
+    mut high_scores: ResMut<HighScores>,
+    recorder: Res<Recorder>,
     q_player: Query<&Player>,
-    mut q_cam: Query<&mut Transform, With<MainCamera>>,
 ) {
-    if let Ok(p) = q_player.single() {
-        if let Ok(mut cam_t) = q_cam.single_mut() {
-            cam_t.translation.y = p.maxheight
+    if let GameMode::TimeAttack(target) = *mode {
+        if let Some(top) = top_height(q_player.iter().map(|p| p.maxheight)) {
+            while top >= (timer.splits.len() + 1) as f32 * SPLIT_HEIGHT
+                && ((timer.splits.len() + 1) as f32 * SPLIT_HEIGHT) <= target as f32
+            {
+                let elapsed = timer.elapsed;
+                timer.splits.push(elapsed);
+            }
+
+            if top >= target as f32 && end_run(&mut app_state, AppState::Results) {
+                let previous = high_scores.time_attack.get(&target).map(|r| r.best);
+                let mut lines = vec![format!("Time: {:.1}s", timer.elapsed)];
+                match previous {
+                    Some(best) if best <= timer.elapsed => {
+                        lines.push(format!("Best: {:.1}s", best));
+                    }
+                    _ => {
+                        lines.push("New best!".to_string());
+                        high_scores.time_attack.insert(
+                            target,
+                            TimeAttackRecord {
+                                best: timer.elapsed,
+                                splits: timer.splits.clone(),
+                            },
+                        );
+                        high_scores.save();
+                        save_replay(
+                            &time_attack_replay_key(target),
+                            &recorder.replay(timer.elapsed),
+                        );
+                    }
+                }
+                for (i, split) in timer.splits.iter().enumerate() {
+                    lines.push(format!(
+                        "{}: {:.1}s",
+                        (i + 1) * SPLIT_HEIGHT as usize,
+                        split
+                    ));
+                }
+                commands.insert_resource(RunResult {
+                    title: format!("Time attack {}", target),
+                    lines,
+                });
+            }
         }
     }
 }
 
-/// Set player dead if too low
-fn player_too_low_system(mut q_player: Query<(&mut Player, &Transform)>) {
-    if let Ok((mut p, t)) = q_player.single_mut() {
-        if p.maxheight - t.translation.y > GAME_BOARD.2.abs() {
-            dbg!("too low");
-            p.dead = true;
+/// Set players dead that fell out of the bottom of the screen
+fn player_too_low_system(
+    mut events: EventWriter<GameEvent>,
+    mut q_player: Query<(&mut Player, &PlayerId, &Transform)>,
+) {
+    if let Some(top) = top_height(q_player.iter_mut().map(|(p, _, _)| p.maxheight)) {
+        for (mut p, id, t) in q_player.iter_mut() {
+            if too_low(top, t.translation.y) {
+                dbg!("too low");
+                if kill(&mut p, DeathCause::Fell) {
+                    events.send(died(id, DeathCause::Fell, t.translation));
+                }
+            }
         }
     }
 }
@@ -392,15 +1114,17 @@ fn player_too_low_system(mut q_player: Query<(&mut Player, &Transform)>) {
 /// Tint the background as you get higher
 fn bg_system(
     mut materials: ResMut<Assets<ColorMaterial>>,
+    physics: Res<Physics>,
     q_player: Query<&Player>,
-    mut q_bg: Query<(&mut Transform, &Sprite, &Handle<ColorMaterial>), With<Background>>,
+    mut q_bg: Query<(&mut Transform, &mut Sprite, &Handle<ColorMaterial>), With<Background>>,
 ) {
-    if let Ok(p) = q_player.single() {
-        if let Ok((mut bg_t, _s, handle)) = q_bg.single_mut() {
-            bg_t.translation.y = p.maxheight;
+    if let Some(top) = top_height(q_player.iter().map(|p| p.maxheight)) {
+        if let Ok((mut bg_t, mut sprite, handle)) = q_bg.single_mut() {
+            bg_t.translation.y = top;
+            sprite.size.x = physics.half_width * 2. + 32.;
             if let Some(mat) = materials.get_mut(handle) {
                 let grad = colorgrad::rainbow();
-                let c = grad.at(p.maxheight as f64 / 10000.);
+                let c = grad.at(top as f64 / 10000.);
 
                 mat.color.set_r(c.r as f32);
                 mat.color.set_g(c.g as f32);
@@ -416,22 +1140,28 @@ fn dist(a: Vec2, b: Vec2) -> f32 {
 }
 
 /// Very simple gravity system
-fn gravity_system(mut player_query: Query<&mut Player>) {
-    if let Ok(mut player) = player_query.single_mut() {
-        player.velocity -= Vec3::Y * GRAVITY_FAC;
+fn gravity_system(mut player_query: Query<&mut Player>, physics: Res<Physics>) {
+    for mut player in player_query.iter_mut() {
+        let player = &mut *player;
+        physics.fall(&mut player.velocity, &mut player.feather);
     }
 }
 
 fn velocity_towards_mine_system(
     mut player_query: Query<(&mut Player, &Transform)>,
     mine_query: Query<(&Mine, &Transform)>,
+    physics: Res<Physics>,
 ) {
-    if let Ok((mut player, p_t)) = player_query.single_mut() {
+    for (mut player, p_t) in player_query.iter_mut() {
         // player.velocity.y *= mine.;
         for (mine, m_t) in mine_query.iter() {
-            if mine.hooked {
-                let dir = m_t.translation - p_t.translation;
-                player.velocity += dir.normalize() * UPWARD_FAC;
+            if player.hooked == Some(mine.id) {
+                physics.pull_player(
+                    &mut player.velocity,
+                    p_t.translation,
+                    m_t.translation,
+                    mine.kind,
+                );
             }
         }
     }
@@ -439,47 +1169,86 @@ fn velocity_towards_mine_system(
 
 /// Drag mines towards players
 fn velocity_towards_player_system(
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(&Player, &Transform)>,
     mut mine_query: Query<(&mut Mine, &Transform)>,
+    physics: Res<Physics>,
 ) {
-    if let Ok(p_t) = player_query.single() {
-        for (mut mine, m_t) in mine_query.iter_mut() {
-            if mine.hooked {
-                let dir = p_t.translation - m_t.translation;
-                mine.velocity += dir.normalize() * 0.04;
-            } else {
-                // slow down mine again if not hooked
-                mine.velocity *= 0.9;
-            }
-        }
+    for (mut mine, m_t) in mine_query.iter_mut() {
+        let pulling: Vec<Vec3> = player_query
+            .iter()
+            .filter(|(p, _)| p.hooked == Some(mine.id))
+            .map(|(_, p_t)| p_t.translation)
+            .collect();
+        let kind = mine.kind;
+        physics.pull_mine(&mut mine.velocity, m_t.translation, &pulling, kind);
     }
 }
 
 fn draw_line_system(
-    q_player: Query<&Transform, (With<Player>, Without<Mine>, Without<Line>)>,
+    q_player: Query<(&Player, &Transform), (Without<Mine>, Without<Line>)>,
     q_mines: Query<(&Transform, &Mine), (Without<Player>, Without<Line>)>,
-    mut q_line: Query<&mut Transform, (With<Line>, Without<Player>, Without<Mine>)>,
+    mut q_line: Query<(&Line, &mut Transform), (Without<Player>, Without<Mine>)>,
 ) {
-    let mut any_hooked = false;
-    if let Ok(p_t) = q_player.single() {
-        for (m_t, mine) in q_mines.iter() {
-            if let Ok(mut l_t) = q_line.single_mut() {
-                if mine.hooked {
-                    let m = m_t.translation.truncate();
-                    let p = p_t.translation.truncate();
-                    l_t.translation = midpoint(m_t.translation, p_t.translation);
-                    l_t.scale.x = dist(m, p);
-                    let diff = m_t.translation - p_t.translation;
-                    let angle = diff.y.atan2(diff.x);
-                    l_t.rotation = Quat::from_axis_angle(Vec3::new(0., 0., 1.), angle);
+    for (line, mut l_t) in q_line.iter_mut() {
+        let mut any_hooked = false;
+        if let Ok((player, p_t)) = q_player.get(line.0) {
+            for (m_t, mine) in q_mines.iter() {
+                if player.hooked == Some(mine.id) {
+                    stretch_line(&mut l_t, p_t.translation, m_t.translation);
                     any_hooked = true;
                 }
             }
         }
         if !any_hooked {
-            if let Ok(mut l_t) = q_line.single_mut() {
-                l_t.scale.x = 0.;
-            }
+            l_t.scale.x = 0.;
+        }
+    }
+}
+
+/// Span a line sprite from the player to a mine
+fn stretch_line(l_t: &mut Transform, player: Vec3, mine: Vec3) {
+    l_t.translation = midpoint(mine, player);
+    l_t.scale.x = dist(mine.truncate(), player.truncate());
+    let diff = mine - player;
+    let angle = diff.y.atan2(diff.x);
+    l_t.rotation = Quat::from_axis_angle(Vec3::new(0., 0., 1.), angle);
+}
+
+/// Advance the ghost along with the real world
+fn ghost_step_system(mut ghost: ResMut<Ghost>) {
+    if let Some(playback) = &mut ghost.0 {
+        playback.step();
+    }
+}
+
+/// Show the ghost and its rope where its own simulation has them
+fn ghost_render_system(
+    ghost: Res<Ghost>,
+    mut q_ghost: Query<(&mut Transform, &mut Visible), (With<GhostPlayer>, Without<GhostLine>)>,
+    mut q_line: Query<(&mut Transform, &mut Visible), (With<GhostLine>, Without<GhostPlayer>)>,
+) {
+    let sim = ghost
+        .0
+        .as_ref()
+        .map(|playback| &playback.sim)
+        .filter(|sim| !sim.over());
+    if let Ok((mut g_t, mut visible)) = q_ghost.single_mut() {
+        visible.is_visible = sim.is_some();
+        if let Some(sim) = sim {
+            // behind the real player and the mines
+            g_t.translation = sim.players[0].position;
+            g_t.translation.z = 0.8;
+        }
+    }
+    if let Ok((mut l_t, mut visible)) = q_line.single_mut() {
+        let rope = sim.and_then(|sim| {
+            let mine = sim.mine(sim.players[0].hooked?)?;
+            Some((sim.players[0].position, mine.position))
+        });
+        visible.is_visible = rope.is_some();
+        if let Some((player, mine)) = rope {
+            stretch_line(&mut l_t, player, mine);
+            l_t.translation.z = 0.8;
         }
     }
 }
@@ -488,9 +1257,9 @@ fn midpoint(a: Vec3, b: Vec3) -> Vec3 {
     Vec3::new((a.x + b.x) / 2., (a.y + b.y) / 2., (a.z + b.z) / 2.)
 }
 
-/// Move player and update the max y position
+/// Move players and update their max y position
 fn player_movement_system(mut player_query: Query<(&mut Player, &mut Transform)>) {
-    if let Ok((mut player, mut transform)) = player_query.single_mut() {
+    for (mut player, mut transform) in player_query.iter_mut() {
         transform.translation += player.velocity;
         player.maxheight = transform.translation.y.max(player.maxheight);
     }
@@ -505,14 +1274,27 @@ fn mine_movement_system(mut mine_query: Query<(&mut Transform, &Mine)>) {
 
 /// Despawn mines that are too low (we'll never need them again)
 fn clean_old_mines_system(
-    mut mine_query: Query<(&mut Transform, Entity), With<Mine>>,
-    player_query: Query<&Player>,
+    mut mine_query: Query<(&mut Transform, &Mine, Entity)>,
+    mut player_query: Query<(&mut Player, &PlayerId)>,
     mut commands: Commands,
+    mut events: EventWriter<GameEvent>,
 ) {
-    if let Ok(player) = player_query.single() {
-        for (transform, mine) in mine_query.iter_mut() {
-            if transform.translation.y < player.maxheight + GAME_BOARD.2 {
-                commands.entity(mine).despawn();
+    if let Some(top) = top_height(player_query.iter_mut().map(|(p, _)| p.maxheight)) {
+        for (transform, mine, entity) in mine_query.iter_mut() {
+            if transform.translation.y < top + GAME_BOARD.2 {
+                commands.entity(entity).despawn();
+                events.send(GameEvent::MineDespawned {
+                    mine: mine.id,
+                    x: transform.translation.x,
+                    y: transform.translation.y,
+                });
+                // which also releases the rope
+                for (mut player, id) in player_query.iter_mut() {
+                    if player.hooked == Some(mine.id) {
+                        player.hooked = None;
+                        events.send(GameEvent::Released { player: id.0 });
+                    }
+                }
             }
         }
     }
@@ -520,39 +1302,108 @@ fn clean_old_mines_system(
 
 /// Trigger state change
 fn is_player_dead_system(
+    mut commands: Commands,
     mut app_state: ResMut<State<AppState>>,
+    mode: Res<GameMode>,
+    mut high_scores: ResMut<HighScores>,
+    mut counted_daily: ResMut<CountedDaily>,
+    mut style: ResMut<StyleScore>,
+    timer: Res<RunTimer>,
+    recorder: Res<Recorder>,
     mut player_query: Query<&mut Player>,
 ) {
-    if let Ok(mut player) = player_query.single_mut() {
-        if player.dead {
-            player.maxheight = 0.;
-            app_state.set(AppState::Menu).unwrap();
+    // the race decides for itself when it is over
+    if matches!(*mode, GameMode::Race(_)) {
+        return;
+    }
+    // the run goes on while anyone is alive
+    let alive = player_query.iter_mut().any(|p| !p.dead());
+    if let Some(top) = top_height(player_query.iter_mut().map(|p| p.maxheight)) {
+        let next = match *mode {
+            GameMode::PlayTest => AppState::Editor,
+            _ => AppState::Results,
+        };
+        if !alive && end_run(&mut app_state, next) {
+            let score = top as i32;
+            if *mode == GameMode::Endless && score > high_scores.endless {
+                high_scores.endless = score;
+                high_scores.save();
+            }
+            if let Some(date) = counted_daily.0.take() {
+                high_scores.finish_daily(&date, score);
+                high_scores.save();
+                // practice runs race against the attempt that counted
+                save_replay(&daily_replay_key(&date), &recorder.replay(timer.elapsed));
+            }
+            for mut player in player_query.iter_mut() {
+                player.maxheight = 0.;
+            }
+            if *mode != GameMode::PlayTest {
+                // records stay about height, the score adds the style on top
+                style.height = top;
+                commands.insert_resource(RunResult {
+                    title: "Game over".to_string(),
+                    lines: style.breakdown(),
+                });
+            }
         }
     }
 }
 
 /// update the score
-fn scoreboard_system(mut query: Query<&mut Text, With<ScoreText>>, player_query: Query<&Player>) {
-    if let Ok(player) = player_query.single() {
-        let mut text = query.single_mut().unwrap();
-        text.sections[0].value = format!("Score: {:}", player.maxheight as i32);
-    }
+fn scoreboard_system(
+    mut query: Query<&mut Text, With<ScoreText>>,
+    player_query: Query<(&Player, &PlayerId)>,
+    mode: Res<GameMode>,
+    timer: Res<RunTimer>,
+    high_scores: Res<HighScores>,
+) {
+    let mut players: Vec<(&Player, &PlayerId)> = player_query.iter().collect();
+    players.sort_by_key(|(_, id)| **id);
+    let mut text = query.single_mut().unwrap();
+    text.sections[0].value = match players.as_slice() {
+        [] => return,
+        // the score adds style on top, it is only known once the run is over
+        [(player, _)] => format!("Height: {:}", player.maxheight as i32),
+        _ => players
+            .iter()
+            .map(|(p, id)| format!("P{}: {}", id.number(), p.maxheight as i32))
+            .collect::<Vec<_>>()
+            .join("  "),
+    };
+    text.sections[1].value = match *mode {
+        GameMode::Endless | GameMode::Daily | GameMode::Race(_) => "".to_string(),
+        GameMode::Level(_) | GameMode::PlayTest => format!("  {:.1}s", timer.elapsed),
+        GameMode::TimeAttack(target) => {
+            let mut value = format!("  {:.1}s", timer.elapsed);
+            // latest split against the personal best
+            if let Some(split) = timer.splits.last() {
+                let i = timer.splits.len() - 1;
+                value += &format!("  {}: {:.1}s", (i + 1) * SPLIT_HEIGHT as usize, split);
+                let best = high_scores.time_attack.get(&target);
+                if let Some(best_split) = best.and_then(|r| r.splits.get(i)) {
+                    value += &format!(" ({:+.1})", split - best_split);
+                }
+            }
+            value
+        }
+    };
 }
 
 /// Very simple wall collision (left/right)
 fn wall_collision_system(
-    mut ball_query: Query<(&mut Player, &mut Transform)>,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
+    mut ball_query: Query<(&mut Player, &PlayerId, &mut Transform)>,
+    physics: Res<Physics>,
+    mut events: EventWriter<GameEvent>,
 ) {
-    if let Ok((mut player, mut p_t)) = ball_query.single_mut() {
+    for (mut player, id, mut p_t) in ball_query.iter_mut() {
         // check collision with walls and "reflect"
-        if p_t.translation.x <= GAME_BOARD.0 || p_t.translation.x >= GAME_BOARD.1 {
-            p_t.translation.x = p_t.translation.x.max(GAME_BOARD.0);
-            p_t.translation.x = p_t.translation.x.min(GAME_BOARD.1);
-            // reverse and dampen
-            player.velocity.x *= -0.5;
-            audio.play(asset_server.load("sfx100v2_metal_01.ogg"));
+        if physics.collide_walls(&mut p_t.translation, &mut player.velocity) {
+            events.send(GameEvent::WallBounce {
+                player: id.0,
+                x: p_t.translation.x,
+                y: p_t.translation.y,
+            });
         }
     }
 }
@@ -569,119 +1420,390 @@ fn wall_collision_system(
 /// clean up everything
 fn end_game_system(
     mut mine_query: Query<Entity, With<Mine>>,
+    level_query: Query<Entity, Or<(With<LevelEntity>, With<RaceEntity>)>>,
     player_query: Query<Entity, With<Player>>,
     cam_query: Query<Entity, With<MainCamera>>,
+    mut ghost_query: Query<&mut Visible, Or<(With<GhostPlayer>, With<GhostLine>)>>,
+    mut ghost: ResMut<Ghost>,
     mut commands: Commands,
 ) {
+    ghost.0 = None;
+    for mut visible in ghost_query.iter_mut() {
+        visible.is_visible = false;
+    }
     // if let Ok(player) = player_query.single() {
     //     commands.entity(player).despawn();
     // }
     for mine in mine_query.iter_mut() {
         commands.entity(mine).despawn();
     }
-    // if let Ok(cam) = cam_query.single() {
-    //     commands.entity(cam).despawn();
-    // }
-}
-
-/// clean up everything
-fn init_game(mut player_query: Query<(&mut Player, &mut Transform)>) {
-    if let Ok((mut player, mut transform)) = player_query.single_mut() {
-        *transform = Transform::from_xyz(0.0, -160.0, 1.0);
-        player.velocity = Vec3::new(0.5, 15.5, 0.0);
-        player.maxheight = 0.0;
-        player.dead = false;
+    for entity in level_query.iter() {
+        commands.entity(entity).despawn();
     }
     // if let Ok(cam) = cam_query.single() {
     //     commands.entity(cam).despawn();
     // }
 }
 
-struct ButtonMaterials {
-    normal: Handle<ColorMaterial>,
-    hovered: Handle<ColorMaterial>,
-    pressed: Handle<ColorMaterial>,
-}
-
-fn setup_menu(
+/// Put a second player next to the first one for a race
+fn init_race(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    button_materials: Res<ButtonMaterials>,
+    materials: Res<PlayerMaterials>,
+    assets: Res<GameAssets>,
+    mode: Res<GameMode>,
+    mut player_query: Query<(&mut Transform, &PlayerId), With<Player>>,
 ) {
-    // ui camera
-    // commands.spawn_bundle(UiCameraBundle::default());
-    let button_entity = commands
-        .spawn_bundle(ButtonBundle {
-            style: Style {
-                size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                // center button
-                margin: Rect::all(Val::Auto),
-                // horizontally center child text
-                justify_content: JustifyContent::Center,
-                // vertically center child text
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            material: button_materials.normal.clone(),
+    let rival = match *mode {
+        GameMode::Race(rival) => rival,
+        _ => return,
+    };
+    for (mut transform, id) in player_query.iter_mut() {
+        if *id == PlayerId(0) {
+            transform.translation.x = -RACE_START_X;
+        }
+    }
+    let (player, line) = spawn_player(
+        &mut commands,
+        &materials,
+        &assets,
+        PlayerId(1),
+        match rival {
+            Rival::Keyboard => Controls::Keyboard,
+            Rival::Bot(_) => Controls::Bot,
+        },
+    );
+    if let Rival::Bot(strategy) = rival {
+        commands.entity(player).insert(Bot::new(strategy));
+    }
+    commands
+        .entity(player)
+        .insert(Transform::from_xyz(RACE_START_X, START_OFFSET, 1.0))
+        .insert(Player {
+            velocity: start_velocity(),
             ..Default::default()
         })
-        .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "Play",
-                    TextStyle {
-                        font: asset_server.load("vcr.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.9, 0.9),
-                    },
-                    Default::default(),
-                ),
-                ..Default::default()
-            });
-        })
-        .id();
-    commands.insert_resource(MenuData { button_entity });
+        .insert(RaceEntity);
+    commands.entity(line).insert(RaceEntity);
 }
 
-impl FromWorld for ButtonMaterials {
-    fn from_world(world: &mut World) -> Self {
-        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
-        ButtonMaterials {
-            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
-            hovered: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
-            pressed: materials.add(Color::rgb(0.35, 0.75, 0.35).into()),
-        }
+/// A race is over once someone reaches RACE_HEIGHT or falls off the screen
+fn race_system(
+    mut commands: Commands,
+    mut app_state: ResMut<State<AppState>>,
+    mode: Res<GameMode>,
+    q_player: Query<(&Player, &PlayerId)>,
+) {
+    if !matches!(*mode, GameMode::Race(_)) {
+        return;
+    }
+    let mut players: Vec<(&Player, &PlayerId)> = q_player.iter().collect();
+    players.sort_by_key(|(_, id)| **id);
+    if !players
+        .iter()
+        .any(|(p, _)| p.dead() || p.maxheight >= RACE_HEIGHT)
+    {
+        return;
     }
+    if !end_run(&mut app_state, AppState::Results) {
+        return;
+    }
+    // whoever is still alive wins, the higher one if that's both
+    let winner = players.iter().filter(|(p, _)| !p.dead()).max_by(|a, b| {
+        a.0.maxheight
+            .partial_cmp(&b.0.maxheight)
+            .unwrap_or(Ordering::Equal)
+    });
+    let title = match winner {
+        Some((_, id)) => format!("Player {} wins!", id.number()),
+        None => "Nobody wins".to_string(),
+    };
+    let lines = players
+        .iter()
+        .map(|(p, id)| format!("Player {}: {}", id.number(), p.maxheight as i32))
+        .collect();
+    commands.insert_resource(RunResult { title, lines });
+}
+
+fn daily_replay_key(date: &str) -> String {
+    format!("replay_daily_{}", date)
+}
+
+fn level_replay_key(name: &str) -> String {
+    format!("replay_level_{}", name)
+}
+
+fn time_attack_replay_key(target: u32) -> String {
+    format!("replay_time_attack_{}", target)
+}
+
+/// Where the personal best of a mode is kept, endless runs and play-tests have none
+fn replay_key(mode: GameMode, active_level: &ActiveLevel) -> Option<String> {
+    match mode {
+        GameMode::Endless | GameMode::PlayTest | GameMode::Race(_) => None,
+        GameMode::Daily => Some(daily_replay_key(&scores::date_string(scores::utc_day()))),
+        GameMode::Level(_) => active_level.0.as_ref().map(|l| level_replay_key(&l.name)),
+        GameMode::TimeAttack(target) => Some(time_attack_replay_key(target)),
+    }
+}
+
+fn load_replay(key: &str) -> Option<Replay> {
+    storage::load(key).and_then(|json| Replay::from_json(&json).ok())
 }
 
-/// Despawn all menu items
-fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
-    commands.entity(menu_data.button_entity).despawn_recursive();
+fn save_replay(key: &str, replay: &Replay) {
+    if let Err(e) = storage::save(key, &replay.to_json()) {
+        warn!("Could not save replay: {}", e);
+    }
 }
 
-fn menu(
-    mut state: ResMut<State<AppState>>,
-    button_materials: Res<ButtonMaterials>,
-    mut interaction_query: Query<
-        (&Interaction, &mut Handle<ColorMaterial>),
-        (Changed<Interaction>, With<Button>),
-    >,
+/// clean up everything
+fn init_game(
+    mut commands: Commands,
+    level_materials: Res<LevelMaterials>,
+    mine_materials: Res<MineMaterials>,
+    mode: Res<GameMode>,
+    levels: Res<Levels>,
+    editor: Res<EditorState>,
+    mut active_level: ResMut<ActiveLevel>,
+    mut timer: ResMut<RunTimer>,
+    mut layout: ResMut<MineLayout>,
+    mut high_scores: ResMut<HighScores>,
+    mut counted_daily: ResMut<CountedDaily>,
+    mut physics: ResMut<Physics>,
+    mut hook_input: ResMut<HookInput>,
+    mut recorder: ResMut<Recorder>,
+    mut ghost: ResMut<Ghost>,
+    mut player_query: Query<(&mut Player, &mut Transform)>,
 ) {
-    for (interaction, mut material) in interaction_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                *material = button_materials.pressed.clone();
-                state.set(AppState::Game).unwrap();
-            }
-            Interaction::Hovered => {
-                *material = button_materials.hovered.clone();
-            }
-            Interaction::None => {
-                *material = button_materials.normal.clone();
+    // play-tests start where the editor camera was
+    let start_height = match *mode {
+        GameMode::PlayTest => editor.playtest_from,
+        _ => 0.0,
+    };
+    for (mut player, mut transform) in player_query.iter_mut() {
+        *transform = Transform::from_xyz(0.0, start_height + START_OFFSET, 1.0);
+        player.velocity = start_velocity();
+        player.maxheight = start_height;
+        player.death = None;
+        player.feather = 0.0;
+        player.hooked = None;
+    }
+    *timer = RunTimer::default();
+    hook_input.pending.clear();
+    physics.half_width = match *mode {
+        GameMode::Race(_) => RACE_HALF_WIDTH,
+        _ => GAME_BOARD.1,
+    };
+
+    active_level.0 = match *mode {
+        GameMode::Endless | GameMode::Daily | GameMode::TimeAttack(_) | GameMode::Race(_) => None,
+        GameMode::Level(i) => Some(levels.0[i].clone()),
+        GameMode::PlayTest => Some(editor.level.clone()),
+    };
+    let best = replay_key(*mode, &active_level).and_then(|key| load_replay(&key));
+
+    counted_daily.0 = None;
+    let seed = match *mode {
+        GameMode::Daily => {
+            let day = scores::utc_day();
+            let date = scores::date_string(day);
+            if high_scores.start_daily(&date) {
+                counted_daily.0 = Some(date);
             }
+            high_scores.save();
+            scores::daily_seed(day)
         }
+        // race the personal best on its own layout
+        GameMode::TimeAttack(_) => match &best {
+            Some(replay) => replay.seed,
+            None => rand::thread_rng().gen(),
+        },
+        _ => rand::thread_rng().gen(),
+    };
+    *layout = MineLayout::new(seed);
+    recorder.0 = Replay {
+        seed,
+        ..Default::default()
+    };
+    ghost.0 = best.map(|replay| {
+        let sim = match &active_level.0 {
+            Some(level) => Simulation::level(level, *physics),
+            None => Simulation::endless(replay.seed, *physics),
+        };
+        Playback::new(sim, &replay)
+    });
+
+    if let Some(level) = &active_level.0 {
+        spawn_level(&mut commands, &level_materials, &mine_materials, level);
     }
+    // if let Ok(cam) = cam_query.single() {
+    //     commands.entity(cam).despawn();
+    // }
 }
-struct MenuData {
-    button_entity: Entity,
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::AssetPlugin;
+
+    use super::*;
+
+    const SOAK_TICKS: u32 = 100_000;
+    // runs are cut short to start over this often
+    const RUN_TICKS: u32 = 2_000;
+    // every player goes up this much per tick
+    const CLIMB: f32 = 10.;
+
+    #[derive(Default)]
+    struct SoakRun {
+        runs: u32,
+        ticks: u32,
+        raced: bool,
+    }
+
+    /// Climb every player and hook the closest mine, the way the game
+    /// highlights and bursts particles for it, and go back to the menu once
+    /// the run is long enough
+    fn soak_system(
+        mut state: ResMut<State<AppState>>,
+        mut run: ResMut<SoakRun>,
+        mut events: EventWriter<GameEvent>,
+        mut q_player: Query<(&mut Player, &mut Transform, &PlayerId)>,
+        q_mine: Query<(&Mine, &Transform), Without<Player>>,
+    ) {
+        for (mut player, mut p_t, id) in q_player.iter_mut() {
+            p_t.translation.y += CLIMB;
+            player.maxheight = p_t.translation.y;
+            let closest = q_mine.iter().min_by(|(_, a), (_, b)| {
+                let a = a.translation.distance(p_t.translation);
+                let b = b.translation.distance(p_t.translation);
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            });
+            if let Some((mine, m_t)) = closest {
+                if player.hooked != Some(mine.id) {
+                    player.hooked = Some(mine.id);
+                    events.send(GameEvent::Hooked {
+                        player: id.0,
+                        mine: mine.id,
+                        x: m_t.translation.x,
+                        y: m_t.translation.y,
+                    });
+                }
+            }
+        }
+        run.raced |= q_player.iter().count() > 1;
+        run.ticks += 1;
+        if run.ticks % RUN_TICKS == 0 {
+            state.set(AppState::Menu).unwrap();
+        }
+    }
+
+    /// Pick the next mode round robin and start it
+    fn restart_system(
+        mut state: ResMut<State<AppState>>,
+        mut mode: ResMut<GameMode>,
+        mut run: ResMut<SoakRun>,
+    ) {
+        *mode = match run.runs % 3 {
+            0 => GameMode::Endless,
+            1 => GameMode::Race(Rival::Bot(Strategy::Greedy)),
+            _ => GameMode::Level(0),
+        };
+        run.runs += 1;
+        state.set(AppState::Game).unwrap();
+    }
+
+    fn setup_soak(
+        mut commands: Commands,
+        materials: Res<PlayerMaterials>,
+        assets: Res<GameAssets>,
+    ) {
+        spawn_player(
+            &mut commands,
+            &materials,
+            &assets,
+            PlayerId(0),
+            Controls::Pointer,
+        );
+    }
+
+    #[test]
+    fn materials_stay_bounded() {
+        let mut app = App::build();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Texture>()
+            .add_asset::<ColorMaterial>();
+        let texture = Handle::<Texture>::default();
+        let (mine_materials, player_materials) = {
+            let mut assets = app
+                .world_mut()
+                .get_resource_mut::<Assets<ColorMaterial>>()
+                .unwrap();
+            (
+                MineMaterials::new(texture.clone(), &mut assets),
+                PlayerMaterials::new(texture.clone(), &mut assets),
+            )
+        };
+        // nothing is loaded, the materials only need the handles
+        app.insert_resource(GameAssets {
+            player: texture.clone(),
+            player_sheet: texture.clone(),
+            player_atlas: Handle::default(),
+            mine: texture,
+            font: Handle::default(),
+            music: Handle::default(),
+            hook_sound: Handle::default(),
+            wall_sound: Handle::default(),
+        })
+        .insert_resource(mine_materials)
+        .insert_resource(player_materials)
+        .init_resource::<LevelMaterials>()
+        .init_resource::<Levels>()
+        .init_resource::<ParticleConfig>()
+        .init_resource::<ParticleMaterials>()
+        .init_resource::<Physics>()
+        .init_resource::<GameMode>()
+        .init_resource::<EditorState>()
+        .init_resource::<ActiveLevel>()
+        .init_resource::<RunTimer>()
+        .init_resource::<HighScores>()
+        .init_resource::<CountedDaily>()
+        .init_resource::<HookInput>()
+        .init_resource::<Recorder>()
+        .init_resource::<Ghost>()
+        .init_resource::<SoakRun>()
+        .insert_resource(MineLayout::new(0))
+        .add_event::<GameEvent>()
+        .add_state(AppState::Menu)
+        .add_startup_system(setup_soak.system())
+        .add_startup_system(setup_particles.system())
+        .add_system_set(SystemSet::on_update(AppState::Menu).with_system(restart_system.system()))
+        // the same systems the game starts, runs and ends runs with
+        .add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(init_game.system().label("init_game"))
+                .with_system(init_race.system().after("init_game")),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Game)
+                .with_system(soak_system.system().label("soak"))
+                .with_system(spawn_new_mine_system.system().after("soak"))
+                .with_system(clean_old_mines_system.system().after("soak"))
+                .with_system(mine_highlighter_system.system().after("soak"))
+                .with_system(particle_system.system().after("soak")),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Game).with_system(end_game_system.system()));
+
+        let count = |world: &World| world.get_resource::<Assets<ColorMaterial>>().unwrap().len();
+        app.app.update();
+        let materials = count(&app.app.world);
+        for _ in 1..SOAK_TICKS {
+            app.app.update();
+        }
+        let run = app.app.world.get_resource::<SoakRun>().unwrap();
+        // runs really did start over in every mode, races included
+        assert!(run.runs > 3);
+        assert!(run.raced);
+        assert_eq!(count(&app.app.world), materials);
+    }
 }
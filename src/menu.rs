@@ -0,0 +1,440 @@
+use bevy::prelude::*;
+
+use crate::achievements::{Achievement, Achievements};
+use crate::assets::GameAssets;
+use crate::bot::Strategy;
+use crate::scores::{self, HighScores};
+use crate::settings::{Setting, Settings};
+use crate::{AppState, GameMode, Levels, Rival, RunResult, TIME_ATTACK_TARGETS};
+
+pub(crate) struct ButtonMaterials {
+    normal: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+    pressed: Handle<ColorMaterial>,
+    background: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ButtonMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ButtonMaterials {
+            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            hovered: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
+            pressed: materials.add(Color::rgb(0.35, 0.75, 0.35).into()),
+            background: materials.add(Color::NONE.into()),
+        }
+    }
+}
+
+/// What a button does when clicked
+#[derive(Clone, Copy)]
+pub(crate) enum MenuButton {
+    Play(GameMode),
+    Modes,
+    Editor,
+    Settings,
+    Toggle(Setting),
+    Achievements,
+    NextSkin,
+    NextRope,
+    Back,
+}
+
+pub(crate) struct MenuData {
+    root: Entity,
+}
+
+/// Full screen column that centers its children
+fn root_node(button_materials: &ButtonMaterials) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            // top to bottom
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: button_materials.background.clone(),
+        ..Default::default()
+    }
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    assets: &GameAssets,
+    button_materials: &ButtonMaterials,
+    label: &str,
+    action: MenuButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(340.0), Val::Px(45.0)),
+                margin: Rect::all(Val::Px(4.0)),
+                // horizontally center child text
+                justify_content: JustifyContent::Center,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 24.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+}
+
+fn spawn_label(parent: &mut ChildBuilder, assets: &GameAssets, label: &str) {
+    parent.spawn_bundle(TextBundle {
+        style: Style {
+            margin: Rect::all(Val::Px(4.0)),
+            ..Default::default()
+        },
+        text: Text::with_section(
+            label,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 24.0,
+                color: Color::rgb(0.15, 0.15, 0.15),
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    });
+}
+
+/// Endless mode and the other screens, short enough for the smallest window
+pub(crate) fn setup_menu(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    button_materials: Res<ButtonMaterials>,
+    high_scores: Res<HighScores>,
+) {
+    let root = commands
+        .spawn_bundle(root_node(&button_materials))
+        .with_children(|parent| {
+            spawn_label(
+                parent,
+                &assets,
+                &format!("Best height: {}", high_scores.endless),
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Endless",
+                MenuButton::Play(GameMode::Endless),
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "More modes",
+                MenuButton::Modes,
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Editor",
+                MenuButton::Editor,
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Achievements",
+                MenuButton::Achievements,
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Settings",
+                MenuButton::Settings,
+            );
+        })
+        .id();
+    commands.insert_resource(MenuData { root });
+}
+
+/// The daily challenge, time attacks, races and the level select
+pub(crate) fn setup_modes(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    button_materials: Res<ButtonMaterials>,
+    levels: Res<Levels>,
+    high_scores: Res<HighScores>,
+) {
+    let date = scores::date_string(scores::utc_day());
+    // after the attempt that counts, the daily can still be practiced
+    let daily_label = match high_scores.daily.get(&date).and_then(|r| r.best) {
+        Some(best) => format!("Daily practice ({})", best),
+        None => format!("Daily {}", date),
+    };
+    let root = commands
+        .spawn_bundle(root_node(&button_materials))
+        .with_children(|parent| {
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                &daily_label,
+                MenuButton::Play(GameMode::Daily),
+            );
+            for target in TIME_ATTACK_TARGETS.iter() {
+                let label = match high_scores.time_attack.get(target) {
+                    Some(record) => format!("Time attack {} ({:.1}s)", target, record.best),
+                    None => format!("Time attack {}", target),
+                };
+                spawn_button(
+                    parent,
+                    &assets,
+                    &button_materials,
+                    &label,
+                    MenuButton::Play(GameMode::TimeAttack(*target)),
+                );
+            }
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Race: mouse vs W",
+                MenuButton::Play(GameMode::Race(Rival::Keyboard)),
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Race: mouse vs bot",
+                MenuButton::Play(GameMode::Race(Rival::Bot(Strategy::Greedy))),
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                "Race: mouse vs lookahead bot",
+                MenuButton::Play(GameMode::Race(Rival::Bot(Strategy::Lookahead))),
+            );
+            for (i, level) in levels.0.iter().enumerate() {
+                spawn_button(
+                    parent,
+                    &assets,
+                    &button_materials,
+                    &level.name,
+                    MenuButton::Play(GameMode::Level(i)),
+                );
+            }
+            spawn_button(parent, &assets, &button_materials, "Back", MenuButton::Back);
+        })
+        .id();
+    commands.insert_resource(MenuData { root });
+}
+
+/// Shown after finishing a level or a time attack
+pub(crate) fn setup_results(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    button_materials: Res<ButtonMaterials>,
+    result: Res<RunResult>,
+) {
+    let root = commands
+        .spawn_bundle(root_node(&button_materials))
+        .with_children(|parent| {
+            spawn_label(parent, &assets, &result.title);
+            for line in result.lines.iter() {
+                spawn_label(parent, &assets, line);
+            }
+            spawn_button(parent, &assets, &button_materials, "Back", MenuButton::Back);
+        })
+        .id();
+    commands.insert_resource(MenuData { root });
+}
+
+/// A toggle for every setting
+pub(crate) fn setup_settings(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    button_materials: Res<ButtonMaterials>,
+    settings: Res<Settings>,
+) {
+    let root = commands
+        .spawn_bundle(root_node(&button_materials))
+        .with_children(|parent| {
+            for setting in Setting::ALL.iter() {
+                spawn_button(
+                    parent,
+                    &assets,
+                    &button_materials,
+                    &settings.label(*setting),
+                    MenuButton::Toggle(*setting),
+                );
+            }
+            spawn_button(parent, &assets, &button_materials, "Back", MenuButton::Back);
+        })
+        .id();
+    commands.insert_resource(MenuData { root });
+}
+
+/// Keep the toggles saying what they are set to
+pub(crate) fn settings_label_system(
+    settings: Res<Settings>,
+    q_button: Query<(&MenuButton, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (button, children) in q_button.iter() {
+        if let MenuButton::Toggle(setting) = button {
+            for child in children.iter() {
+                if let Ok(mut text) = q_text.get_mut(*child) {
+                    text.sections[0].value = settings.label(*setting);
+                }
+            }
+        }
+    }
+}
+
+/// What the cosmetic buttons say
+fn skin_label(achievements: &Achievements) -> String {
+    format!("Skin: {}", achievements.skin().name)
+}
+
+fn rope_label(achievements: &Achievements) -> String {
+    format!("Rope: {}", achievements.rope().name)
+}
+
+/// Every achievement, and the looks they unlock
+pub(crate) fn setup_achievements(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    button_materials: Res<ButtonMaterials>,
+    achievements: Res<Achievements>,
+) {
+    let root = commands
+        .spawn_bundle(root_node(&button_materials))
+        .with_children(|parent| {
+            for achievement in Achievement::ALL.iter() {
+                spawn_label(parent, &assets, &achievements.label(*achievement));
+            }
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                &skin_label(&achievements),
+                MenuButton::NextSkin,
+            );
+            spawn_button(
+                parent,
+                &assets,
+                &button_materials,
+                &rope_label(&achievements),
+                MenuButton::NextRope,
+            );
+            spawn_button(parent, &assets, &button_materials, "Back", MenuButton::Back);
+        })
+        .id();
+    commands.insert_resource(MenuData { root });
+}
+
+/// Keep the cosmetic buttons saying what is picked
+pub(crate) fn cosmetics_label_system(
+    achievements: Res<Achievements>,
+    q_button: Query<(&MenuButton, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    if !achievements.is_changed() {
+        return;
+    }
+    for (button, children) in q_button.iter() {
+        let label = match button {
+            MenuButton::NextSkin => skin_label(&achievements),
+            MenuButton::NextRope => rope_label(&achievements),
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = q_text.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+/// Despawn all menu items
+pub(crate) fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
+    commands.entity(menu_data.root).despawn_recursive();
+}
+
+pub(crate) fn menu(
+    mut state: ResMut<State<AppState>>,
+    mut mode: ResMut<GameMode>,
+    mut settings: ResMut<Settings>,
+    mut achievements: ResMut<Achievements>,
+    button_materials: Res<ButtonMaterials>,
+    mut interaction_query: Query<
+        (&Interaction, &mut Handle<ColorMaterial>, &MenuButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, mut material, action) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *material = button_materials.pressed.clone();
+                match *action {
+                    MenuButton::Play(m) => {
+                        *mode = m;
+                        state.set(AppState::Game).unwrap();
+                    }
+                    MenuButton::Modes => {
+                        state.set(AppState::Modes).unwrap();
+                    }
+                    MenuButton::Editor => {
+                        state.set(AppState::Editor).unwrap();
+                    }
+                    MenuButton::Settings => {
+                        state.set(AppState::Settings).unwrap();
+                    }
+                    MenuButton::Toggle(setting) => {
+                        settings.toggle(setting);
+                        settings.save();
+                    }
+                    MenuButton::Achievements => {
+                        state.set(AppState::Achievements).unwrap();
+                    }
+                    MenuButton::NextSkin => {
+                        achievements.next_skin();
+                        achievements.save();
+                    }
+                    MenuButton::NextRope => {
+                        achievements.next_rope();
+                        achievements.save();
+                    }
+                    MenuButton::Back => {
+                        state.set(AppState::Menu).unwrap();
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *material = button_materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = button_materials.normal.clone();
+            }
+        }
+    }
+}
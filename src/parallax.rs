@@ -0,0 +1,225 @@
+// Background layers that scroll slower than the world, each one tiled
+// endlessly upwards with mountains near the ground, clouds in the sky and
+// stars further up
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::physics::{Physics, GAME_BOARD};
+use crate::MainCamera;
+
+// taller than anything the camera shows, so three tiles always cover it
+const TILE_HEIGHT: f32 = 1200.;
+const TILES_PER_LAYER: i64 = 3;
+// altitudes where the sky and then space begin
+const ZONES: [f32; 2] = [2000., 8000.];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Content {
+    Empty,
+    Mountains,
+    Clouds,
+    Stars,
+}
+
+struct Layer {
+    /// How fast it moves compared to the world, 1 would be glued to it
+    factor: f32,
+    /// Behind the mines and players, in front of the background
+    z: f32,
+    /// What it shows near the ground, in the sky and in space
+    zones: [Content; 3],
+}
+
+const LAYERS: [Layer; 3] = [
+    Layer {
+        factor: 0.1,
+        z: 0.1,
+        zones: [Content::Mountains, Content::Empty, Content::Stars],
+    },
+    Layer {
+        factor: 0.3,
+        z: 0.2,
+        zones: [Content::Clouds, Content::Clouds, Content::Stars],
+    },
+    Layer {
+        factor: 0.6,
+        z: 0.3,
+        zones: [Content::Empty, Content::Clouds, Content::Empty],
+    },
+];
+
+/// Which of the ZONES an altitude is in
+fn zone(altitude: f32) -> usize {
+    ZONES.iter().filter(|start| altitude >= **start).count()
+}
+
+/// One tile of a layer, showing the tile `index` of the layer counted from the ground
+pub(crate) struct ParallaxTile {
+    layer: usize,
+    /// Takes every TILES_PER_LAYER-th tile, starting at this one
+    slot: i64,
+    index: Option<i64>,
+}
+
+pub(crate) struct ParallaxMaterials {
+    mountain: Handle<ColorMaterial>,
+    cloud: Handle<ColorMaterial>,
+    star: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ParallaxMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ParallaxMaterials {
+            mountain: materials.add(Color::rgba(0.2, 0.2, 0.35, 0.5).into()),
+            cloud: materials.add(Color::rgba(1.0, 1.0, 1.0, 0.35).into()),
+            star: materials.add(Color::rgba(1.0, 1.0, 0.9, 0.8).into()),
+        }
+    }
+}
+
+/// A shape in a tile, relative to its center
+struct Shape {
+    position: Vec2,
+    size: Vec2,
+    /// Radians
+    rotation: f32,
+}
+
+/// The same tile always looks the same
+fn shapes(content: Content, layer: usize, index: i64, half_width: f32) -> Vec<Shape> {
+    let mut rng = StdRng::seed_from_u64((index as u64) << 2 | layer as u64);
+    let half_height = TILE_HEIGHT / 2.;
+    let mut shapes = Vec::new();
+    match content {
+        Content::Empty => {}
+        Content::Mountains => {
+            // squares turned on their corner, the top halves make the peaks,
+            // standing on the bottom of the screen at the start
+            let mut x = -half_width;
+            while x < half_width {
+                let size = rng.gen_range(300.0..600.0);
+                shapes.push(Shape {
+                    position: Vec2::new(x, -half_height + GAME_BOARD.2),
+                    size: Vec2::splat(size),
+                    rotation: std::f32::consts::FRAC_PI_4,
+                });
+                x += size * rng.gen_range(0.5..0.9);
+            }
+        }
+        Content::Clouds => {
+            for _ in 0..rng.gen_range(2..5) {
+                let center = Vec2::new(
+                    rng.gen_range(-half_width..half_width),
+                    rng.gen_range(-half_height..half_height),
+                );
+                // a few overlapping puffs
+                for _ in 0..rng.gen_range(3..6) {
+                    let offset = Vec2::new(rng.gen_range(-40.0..40.0), rng.gen_range(-10.0..10.0));
+                    shapes.push(Shape {
+                        position: center + offset,
+                        size: Vec2::new(rng.gen_range(40.0..90.0), rng.gen_range(20.0..35.0)),
+                        rotation: 0.,
+                    });
+                }
+            }
+        }
+        Content::Stars => {
+            for _ in 0..rng.gen_range(20..40) {
+                shapes.push(Shape {
+                    position: Vec2::new(
+                        rng.gen_range(-half_width..half_width),
+                        rng.gen_range(-half_height..half_height),
+                    ),
+                    size: Vec2::splat(rng.gen_range(1.5..3.5)),
+                    rotation: 0.,
+                });
+            }
+        }
+    }
+    shapes
+}
+
+pub(crate) fn setup_parallax(mut commands: Commands) {
+    for layer in 0..LAYERS.len() {
+        for slot in 0..TILES_PER_LAYER {
+            commands
+                .spawn()
+                .insert(Transform::default())
+                .insert(GlobalTransform::default())
+                .insert(ParallaxTile {
+                    layer,
+                    slot,
+                    index: None,
+                });
+        }
+    }
+}
+
+/// Move the tiles along with the camera at their layer's pace and give tiles
+/// that scrolled out of view the content of the ones coming in
+pub(crate) fn parallax_system(
+    mut commands: Commands,
+    physics: Res<Physics>,
+    materials: Res<ParallaxMaterials>,
+    q_cam: Query<&Transform, (With<MainCamera>, Without<ParallaxTile>)>,
+    mut q_tile: Query<(Entity, &mut ParallaxTile, &mut Transform, Option<&Children>)>,
+) {
+    let cam_y = match q_cam.single() {
+        Ok(cam_t) => cam_t.translation.y,
+        Err(_) => return,
+    };
+    for (entity, mut tile, mut transform, children) in q_tile.iter_mut() {
+        let layer = &LAYERS[tile.layer];
+
+        // the tiles around the one the camera is in
+        let scrolled = cam_y * layer.factor;
+        let first = (scrolled / TILE_HEIGHT).floor() as i64 - 1;
+        let index = first + (tile.slot - first).rem_euclid(TILES_PER_LAYER);
+
+        transform.translation = Vec3::new(
+            0.,
+            (index as f32 + 0.5) * TILE_HEIGHT + cam_y - scrolled,
+            layer.z,
+        );
+
+        if tile.index == Some(index) {
+            continue;
+        }
+        tile.index = Some(index);
+        if let Some(children) = children {
+            for child in children.iter() {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+        // the altitude the camera is at when the bottom of this tile is in the middle
+        // of the screen, nothing below the ground
+        let altitude = index as f32 * TILE_HEIGHT / layer.factor;
+        let content = match index {
+            i if i < 0 => Content::Empty,
+            _ => layer.zones[zone(altitude)],
+        };
+        let material = match content {
+            Content::Empty => continue,
+            Content::Mountains => materials.mountain.clone(),
+            Content::Clouds => materials.cloud.clone(),
+            Content::Stars => materials.star.clone(),
+        };
+        let half_width = physics.half_width.max(GAME_BOARD.1);
+        commands.entity(entity).with_children(|parent| {
+            for shape in shapes(content, tile.layer, index, half_width) {
+                parent.spawn_bundle(SpriteBundle {
+                    material: material.clone(),
+                    sprite: Sprite::new(shape.size),
+                    transform: Transform {
+                        translation: shape.position.extend(0.),
+                        rotation: Quat::from_rotation_z(shape.rotation),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+        });
+    }
+}
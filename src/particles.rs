@@ -0,0 +1,214 @@
+// Small pooled sprite particles for hooks, wall bounces, mines leaving and
+// deaths, what each of them looks like is in assets/particles.json
+
+use bevy::log::warn;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::sim::GameEvent;
+
+// native builds read this at startup, so tweaks only need a restart, the
+// copy built in is for the web and for when the file is missing or broken
+#[cfg(not(target_arch = "wasm32"))]
+const CONFIG_PATH: &str = "assets/particles.json";
+const CONFIG: &str = include_str!("../assets/particles.json");
+// particles alive at once, anything more is dropped
+const POOL_SIZE: usize = 256;
+// in front of players and mines
+const PARTICLE_Z: f32 = 2.0;
+// colors a particle steps through from start_color to end_color, each one a
+// material shared by all particles of the emitter
+const FADE_STEPS: usize = 8;
+
+/// What one kind of burst looks like
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Emitter {
+    count: u32,
+    /// Units per second, smallest and largest
+    speed: (f32, f32),
+    /// Seconds
+    lifetime: f32,
+    size: f32,
+    start_color: [f32; 4],
+    end_color: [f32; 4],
+    /// Units per second squared pulling the particles down
+    #[serde(default)]
+    gravity: f32,
+}
+
+impl Emitter {
+    fn particle<R: Rng>(&self, emitter: usize, rng: &mut R) -> Particle {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let speed = rng.gen_range(self.speed.0..=self.speed.1);
+        Particle {
+            velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+            gravity: self.gravity,
+            age: 0.0,
+            lifetime: self.lifetime,
+            emitter,
+        }
+    }
+
+    fn fade(&self, step: usize) -> Color {
+        let t = step as f32 / (FADE_STEPS - 1) as f32;
+        Vec4::from(self.start_color)
+            .lerp(Vec4::from(self.end_color), t)
+            .into()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ParticleConfig {
+    hook: Emitter,
+    wall_bounce: Emitter,
+    mine_despawn: Emitter,
+    death: Emitter,
+}
+
+/// The config file on disk, natively
+#[cfg(not(target_arch = "wasm32"))]
+fn read_config() -> Option<String> {
+    std::fs::read_to_string(CONFIG_PATH).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_config() -> Option<String> {
+    None
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        if let Some(json) = read_config() {
+            match serde_json::from_str(&json) {
+                Ok(config) => return config,
+                Err(e) => warn!("Particle config is broken, using the built in one: {}", e),
+            }
+        }
+        serde_json::from_str(CONFIG).expect("Bundled particle config is broken")
+    }
+}
+
+impl ParticleConfig {
+    /// Particles refer to their emitter by its index in here
+    fn emitters(&self) -> [&Emitter; 4] {
+        [
+            &self.hook,
+            &self.wall_bounce,
+            &self.mine_despawn,
+            &self.death,
+        ]
+    }
+}
+
+/// FADE_STEPS materials per emitter, in the order of ParticleConfig::emitters
+pub(crate) struct ParticleMaterials(Vec<Vec<Handle<ColorMaterial>>>);
+
+impl FromWorld for ParticleMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.get_resource::<ParticleConfig>().unwrap().clone();
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ParticleMaterials(
+            config
+                .emitters()
+                .iter()
+                .map(|emitter| {
+                    (0..FADE_STEPS)
+                        .map(|step| materials.add(emitter.fade(step).into()))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Idle once its age is past its lifetime, ready to be emitted again
+#[derive(Default)]
+pub(crate) struct Particle {
+    velocity: Vec2,
+    gravity: f32,
+    age: f32,
+    lifetime: f32,
+    emitter: usize,
+}
+
+/// Spawn the whole pool up front, hidden
+pub(crate) fn setup_particles(mut commands: Commands, materials: Res<ParticleMaterials>) {
+    for _ in 0..POOL_SIZE {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.0[0][0].clone(),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(Particle::default());
+    }
+}
+
+/// Emit bursts for the events of this frame into idle particles and move the live ones
+pub(crate) fn particle_system(
+    time: Res<Time>,
+    config: Res<ParticleConfig>,
+    materials: Res<ParticleMaterials>,
+    mut events: EventReader<GameEvent>,
+    mut q_particle: Query<(
+        &mut Particle,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visible,
+        &mut Handle<ColorMaterial>,
+    )>,
+) {
+    let emitters = config.emitters();
+    let mut pending = Vec::new();
+    for event in events.iter() {
+        // indices into emitters
+        let (emitter, x, y) = match *event {
+            GameEvent::Hooked { x, y, .. } => (0, x, y),
+            GameEvent::WallBounce { x, y, .. } => (1, x, y),
+            GameEvent::MineDespawned { x, y, .. } => (2, x, y),
+            GameEvent::Died { x, y, .. } => (3, x, y),
+            _ => continue,
+        };
+        for _ in 0..emitters[emitter].count {
+            pending.push((emitter, Vec2::new(x, y)));
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let dt = time.delta_seconds();
+    for (mut particle, mut transform, mut sprite, mut visible, mut material) in
+        q_particle.iter_mut()
+    {
+        if particle.age >= particle.lifetime {
+            match pending.pop() {
+                Some((emitter, at)) => {
+                    *particle = emitters[emitter].particle(emitter, &mut rng);
+                    transform.translation = at.extend(PARTICLE_Z);
+                    sprite.size = Vec2::splat(emitters[emitter].size);
+                    visible.is_visible = true;
+                }
+                None => {
+                    if visible.is_visible {
+                        visible.is_visible = false;
+                    }
+                    continue;
+                }
+            }
+        } else {
+            particle.age += dt;
+            particle.velocity.y -= particle.gravity * dt;
+            transform.translation += particle.velocity.extend(0.0) * dt;
+        }
+        let life = (particle.age / particle.lifetime).min(1.0);
+        let step = (life * (FADE_STEPS - 1) as f32).round() as usize;
+        let fade = &materials.0[particle.emitter][step];
+        // swap handles instead of recoloring, so nothing is uploaded every frame
+        if *material != *fade {
+            *material = fade.clone();
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::math::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::level::PickupKind;
+
+// everything that moves does so in fixed ticks, so runs can be replayed exactly
+pub const TIME_STEP: f32 = 1.0 / 30.0;
+// left, right, bottom, top
+pub const GAME_BOARD: (f32, f32, f32, f32) = (-200.0, 200.0, -400.0, 400.0);
+// const GRAVITY_FAC: f32 = 0.09;
+pub const GRAVITY_FAC: f32 = 0.18;
+// const UPWARD_FAC: f32 = 0.25;
+pub const UPWARD_FAC: f32 = 0.5;
+// how hard a hooked mine is dragged towards the player
+pub const MINE_PULL_FAC: f32 = 0.04;
+// how fast an unhooked mine slows down again
+pub const MINE_DAMPING: f32 = 0.9;
+// what is left of the horizontal velocity after hitting a wall
+pub const WALL_BOUNCE: f32 = -0.5;
+
+pub const PLAYER_SIZE: f32 = 32.0;
+pub const PICKUP_SIZE: f32 = 16.0;
+// where a run starts, below the start height
+pub const START_OFFSET: f32 = -160.0;
+// upward speed a boost pickup gives
+pub const BOOST_KICK: f32 = 12.;
+// how long a feather pickup lasts in seconds
+pub const FEATHER_TIME: f32 = 5.;
+// gravity multiplier while a feather is active
+pub const FEATHER_GRAVITY: f32 = 0.5;
+
+/// What a mine does when it is hooked
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MineKind {
+    #[default]
+    Normal,
+    /// Doesn't get dragged towards the player
+    Heavy,
+    /// Pulls the player harder than usual
+    Boost,
+}
+
+impl MineKind {
+    /// Multiplier on the upward pull
+    pub fn strength(&self) -> f32 {
+        match self {
+            MineKind::Boost => 1.6,
+            _ => 1.0,
+        }
+    }
+
+    pub fn movable(&self) -> bool {
+        *self != MineKind::Heavy
+    }
+}
+
+/// All the knobs of the game physics, defaults are the values the game ships with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Physics {
+    pub gravity: f32,
+    pub upward: f32,
+    pub mine_pull: f32,
+    pub mine_damping: f32,
+    pub wall_bounce: f32,
+    /// Walls are this far from the center
+    pub half_width: f32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: GRAVITY_FAC,
+            upward: UPWARD_FAC,
+            mine_pull: MINE_PULL_FAC,
+            mine_damping: MINE_DAMPING,
+            wall_bounce: WALL_BOUNCE,
+            half_width: GAME_BOARD.1,
+        }
+    }
+}
+
+impl Physics {
+    /// Very simple gravity
+    pub fn gravity(&self, velocity: &mut Vec3) {
+        *velocity -= Vec3::Y * self.gravity;
+    }
+
+    /// Gravity, weaker while a feather is active
+    pub fn fall(&self, velocity: &mut Vec3, feather: &mut f32) {
+        let mut gravity = self.gravity;
+        if *feather > 0. {
+            *feather -= TIME_STEP;
+            gravity *= FEATHER_GRAVITY;
+        }
+        *velocity -= Vec3::Y * gravity;
+    }
+
+    /// Accelerate the player towards a hooked mine
+    pub fn pull_player(&self, velocity: &mut Vec3, player: Vec3, mine: Vec3, kind: MineKind) {
+        let dir = mine - player;
+        *velocity += dir.normalize() * self.upward * kind.strength();
+    }
+
+    /// Drag a mine towards every player hooked to it, slow it down again if there are none
+    pub fn pull_mine(&self, velocity: &mut Vec3, mine: Vec3, players: &[Vec3], kind: MineKind) {
+        if !players.is_empty() && kind.movable() {
+            for player in players {
+                let dir = *player - mine;
+                *velocity += dir.normalize() * self.mine_pull;
+            }
+        } else {
+            *velocity *= self.mine_damping;
+        }
+    }
+
+    /// Keep the player between the walls, returns true if a wall was hit
+    pub fn collide_walls(&self, position: &mut Vec3, velocity: &mut Vec3) -> bool {
+        if position.x <= -self.half_width || position.x >= self.half_width {
+            position.x = position.x.max(-self.half_width);
+            position.x = position.x.min(self.half_width);
+            // reverse and dampen
+            velocity.x *= self.wall_bounce;
+            return true;
+        }
+        false
+    }
+}
+
+/// The player dies once it falls out of the bottom of the screen
+pub fn too_low(maxheight: f32, y: f32) -> bool {
+    maxheight - y > GAME_BOARD.2.abs()
+}
+
+/// The little kick every run starts with
+pub fn start_velocity() -> Vec3 {
+    Vec3::new(0.5, 15.5, 0.0)
+}
+
+/// Axis aligned boxes around their centers touch
+pub fn overlaps(a: Vec3, a_size: Vec2, b: Vec3, b_size: Vec2) -> bool {
+    (a.x - b.x).abs() * 2. < a_size.x + b_size.x && (a.y - b.y).abs() * 2. < a_size.y + b_size.y
+}
+
+/// What flying through a pickup does
+pub fn collect(kind: PickupKind, velocity: &mut Vec3, feather: &mut f32) {
+    match kind {
+        PickupKind::Boost => velocity.y = velocity.y.max(0.) + BOOST_KICK,
+        PickupKind::Feather => *feather = FEATHER_TIME,
+    }
+}
+
+/// Mines can only be grabbed while they are on screen
+pub fn on_screen(maxheight: f32, y: f32) -> bool {
+    y <= maxheight + GAME_BOARD.3 && y >= maxheight + GAME_BOARD.2
+}
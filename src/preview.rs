@@ -0,0 +1,116 @@
+// A dotted line of where the player would fly if it hooked the mine under the
+// cursor, played out by the simulation on a copy of the world
+
+use bevy::prelude::*;
+
+use crate::physics::{Physics, TIME_STEP};
+use crate::settings::Settings;
+use crate::sim::{SimMine, SimPlayer, Simulation};
+use crate::{top_height, AppState, Controls, Mine, Player, PlayerId};
+
+// about a second ahead
+const TICKS: u32 = (1. / TIME_STEP) as u32;
+// one dot every few ticks
+const TICKS_PER_DOT: u32 = 2;
+const DOTS: usize = (TICKS / TICKS_PER_DOT) as usize;
+const DOT_SIZE: f32 = 4.;
+// between the rope and the player
+const DOT_Z: f32 = 0.95;
+
+pub(crate) struct PreviewDot(usize);
+
+/// Where `player` is after each of the next `ticks` ticks if it hooks `mine`
+/// now, ends early if it dies, `top` is where the screen is
+pub(crate) fn trajectory(
+    physics: Physics,
+    player: SimPlayer,
+    mines: Vec<SimMine>,
+    top: f32,
+    mine: u32,
+    ticks: u32,
+) -> Vec<Vec3> {
+    let mut sim = Simulation::snapshot(physics, vec![player], mines, top);
+    sim.hook(0, Some(mine));
+    let mut path = Vec::new();
+    for _ in 0..ticks {
+        sim.step();
+        if sim.over() {
+            break;
+        }
+        path.push(sim.players[0].position);
+    }
+    path
+}
+
+pub(crate) fn setup_preview(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let color = PlayerId(0).tint();
+    let material = materials.add(Color::rgba(color.r(), color.g(), color.b(), 0.6).into());
+    for i in 0..DOTS {
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: material.clone(),
+                sprite: Sprite::new(Vec2::splat(DOT_SIZE)),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(PreviewDot(i));
+    }
+}
+
+/// Show the path of the pointer player while a mine is hovered and the setting is on
+pub(crate) fn preview_system(
+    physics: Res<Physics>,
+    settings: Res<Settings>,
+    state: Res<State<AppState>>,
+    q_player: Query<(&Player, &Transform, &Controls), Without<PreviewDot>>,
+    q_mine: Query<(&Mine, &Transform), Without<PreviewDot>>,
+    mut q_dot: Query<(&PreviewDot, &mut Transform, &mut Visible)>,
+) {
+    let hovered = q_mine.iter().find(|(m, _)| m.selected).map(|(m, _)| m.id);
+    let pointer = q_player
+        .iter()
+        .find(|(p, _, controls)| **controls == Controls::Pointer && !p.dead());
+    let path = match (hovered, pointer) {
+        (Some(mine), Some((player, p_t, _)))
+            if settings.trajectory_preview && *state.current() == AppState::Game =>
+        {
+            let player = SimPlayer {
+                position: p_t.translation,
+                velocity: player.velocity,
+                maxheight: player.maxheight,
+                death: None,
+                feather: player.feather,
+                hooked: player.hooked,
+            };
+            let mines = q_mine
+                .iter()
+                .map(|(m, m_t)| SimMine {
+                    id: m.id,
+                    position: m_t.translation,
+                    velocity: m.velocity,
+                    kind: m.kind,
+                })
+                .collect();
+            // the other players keep the screen up too
+            let top = top_height(q_player.iter().map(|(p, _, _)| p.maxheight)).unwrap_or(0.);
+            trajectory(*physics, player, mines, top, mine, TICKS)
+        }
+        _ => Vec::new(),
+    };
+    for (dot, mut dot_t, mut visible) in q_dot.iter_mut() {
+        let tick = (dot.0 + 1) * TICKS_PER_DOT as usize - 1;
+        let shown = match path.get(tick) {
+            Some(position) => {
+                dot_t.translation = position.truncate().extend(DOT_Z);
+                true
+            }
+            None => false,
+        };
+        if visible.is_visible != shown {
+            visible.is_visible = shown;
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use bevy::log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const SAVE_KEY: &str = "highscores";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct DailyRecord {
+    pub(crate) attempts: u32,
+    /// Height reached on the first attempt, the only one that counts
+    pub(crate) best: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct TimeAttackRecord {
+    pub(crate) best: f32,
+    /// Split times of the best run
+    pub(crate) splits: Vec<f32>,
+}
+
+/// Everything worth remembering between sessions
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct HighScores {
+    #[serde(default)]
+    pub(crate) endless: i32,
+    /// Keyed by date, "YYYY-MM-DD"
+    #[serde(default)]
+    pub(crate) daily: BTreeMap<String, DailyRecord>,
+    /// Keyed by target height
+    #[serde(default)]
+    pub(crate) time_attack: BTreeMap<u32, TimeAttackRecord>,
+}
+
+impl HighScores {
+    pub(crate) fn load() -> Self {
+        storage::load(SAVE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self) {
+        let json = serde_json::to_string(self).expect("High scores are always valid JSON");
+        if let Err(e) = storage::save(SAVE_KEY, &json) {
+            warn!("Could not save high scores: {}", e);
+        }
+    }
+
+    /// Count an attempt at the daily challenge, true if it is the first one today
+    pub(crate) fn start_daily(&mut self, date: &str) -> bool {
+        let record = self.daily.entry(date.to_string()).or_default();
+        record.attempts += 1;
+        record.attempts == 1
+    }
+
+    pub(crate) fn finish_daily(&mut self, date: &str, score: i32) {
+        let record = self.daily.entry(date.to_string()).or_default();
+        record.best = Some(record.best.map_or(score, |best| best.max(score)));
+    }
+}
+
+/// Days since 1970-01-01, in UTC
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn utc_day() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+/// Days since 1970-01-01, in UTC
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn utc_day() -> u64 {
+    (js_sys::Date::now() / 86_400_000.) as u64
+}
+
+/// "YYYY-MM-DD" of a day from utc_day
+pub(crate) fn date_string(day: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// Everyone gets the same layout on the same day
+pub(crate) fn daily_seed(day: u64) -> u64 {
+    day.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
@@ -0,0 +1,109 @@
+use bevy::log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const SAVE_KEY: &str = "settings";
+
+/// Everything that can be switched on and off in the settings menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Setting {
+    CameraSmoothing,
+    LookAhead,
+    ScreenShake,
+    SpeedZoom,
+    FitToWindow,
+    TrajectoryPreview,
+}
+
+impl Setting {
+    pub(crate) const ALL: [Setting; 6] = [
+        Setting::CameraSmoothing,
+        Setting::LookAhead,
+        Setting::ScreenShake,
+        Setting::SpeedZoom,
+        Setting::FitToWindow,
+        Setting::TrajectoryPreview,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Setting::CameraSmoothing => "Smooth camera",
+            Setting::LookAhead => "Look ahead",
+            Setting::ScreenShake => "Screen shake",
+            Setting::SpeedZoom => "Zoom out when fast",
+            Setting::FitToWindow => "Fit board to window",
+            Setting::TrajectoryPreview => "Show path when aiming",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    pub(crate) camera_smoothing: bool,
+    pub(crate) look_ahead: bool,
+    pub(crate) screen_shake: bool,
+    pub(crate) speed_zoom: bool,
+    /// Scale the board to the window instead of one unit per pixel
+    pub(crate) fit_to_window: bool,
+    /// Dots where the player would go if it hooked the mine under the cursor
+    pub(crate) trajectory_preview: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            camera_smoothing: true,
+            look_ahead: true,
+            screen_shake: true,
+            speed_zoom: true,
+            fit_to_window: true,
+            trajectory_preview: false,
+        }
+    }
+}
+
+impl Settings {
+    pub(crate) fn load() -> Self {
+        storage::load(SAVE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self) {
+        let json = serde_json::to_string(self).expect("Settings are always valid JSON");
+        if let Err(e) = storage::save(SAVE_KEY, &json) {
+            warn!("Could not save settings: {}", e);
+        }
+    }
+
+    pub(crate) fn get(&self, setting: Setting) -> bool {
+        match setting {
+            Setting::CameraSmoothing => self.camera_smoothing,
+            Setting::LookAhead => self.look_ahead,
+            Setting::ScreenShake => self.screen_shake,
+            Setting::SpeedZoom => self.speed_zoom,
+            Setting::FitToWindow => self.fit_to_window,
+            Setting::TrajectoryPreview => self.trajectory_preview,
+        }
+    }
+
+    pub(crate) fn toggle(&mut self, setting: Setting) {
+        let value = match setting {
+            Setting::CameraSmoothing => &mut self.camera_smoothing,
+            Setting::LookAhead => &mut self.look_ahead,
+            Setting::ScreenShake => &mut self.screen_shake,
+            Setting::SpeedZoom => &mut self.speed_zoom,
+            Setting::FitToWindow => &mut self.fit_to_window,
+            Setting::TrajectoryPreview => &mut self.trajectory_preview,
+        };
+        *value = !*value;
+    }
+
+    /// What the button of a setting says
+    pub(crate) fn label(&self, setting: Setting) -> String {
+        let state = if self.get(setting) { "on" } else { "off" };
+        format!("{}: {}", setting.name(), state)
+    }
+}
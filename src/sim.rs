@@ -0,0 +1,359 @@
+use bevy::math::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::layout::MineLayout;
+use crate::level::{Hazard, Level, Pickup};
+use crate::physics::{
+    self, start_velocity, too_low, MineKind, Physics, GAME_BOARD, PICKUP_SIZE, PLAYER_SIZE,
+    START_OFFSET,
+};
+
+/// A hook or release, as it happened in a run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HookEvent {
+    /// Ticks since the start of the run
+    pub tick: u32,
+    /// Index of the player that hooked
+    #[serde(default)]
+    pub player: u8,
+    /// The hooked mine, None for a release
+    pub mine: Option<u32>,
+}
+
+/// Everything needed to play a run again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    /// Seed of the mine layout, unused in levels
+    pub seed: u64,
+    /// Seconds the run took
+    pub time: f32,
+    pub events: Vec<HookEvent>,
+}
+
+impl Replay {
+    pub fn from_json(json: &str) -> Result<Replay, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Replays are always valid JSON")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimMine {
+    pub id: u32,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub kind: MineKind,
+}
+
+/// What ended a player's run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathCause {
+    /// Fell out of the bottom of the screen
+    Fell,
+    /// Flew into a hazard
+    Hazard,
+}
+
+/// Something that happened in a run, for telemetry, sounds and effects
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// Rope attached to the mine at x, y
+    Hooked {
+        player: u8,
+        mine: u32,
+        x: f32,
+        y: f32,
+    },
+    Released {
+        player: u8,
+    },
+    WallBounce {
+        player: u8,
+        x: f32,
+        y: f32,
+    },
+    MineSpawned {
+        mine: u32,
+        x: f32,
+        y: f32,
+    },
+    /// Left through the bottom of the screen
+    MineDespawned {
+        mine: u32,
+        x: f32,
+        y: f32,
+    },
+    Died {
+        player: u8,
+        cause: DeathCause,
+        x: f32,
+        y: f32,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SimPlayer {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub maxheight: f32,
+    pub death: Option<DeathCause>,
+    // seconds of feather left
+    pub feather: f32,
+    pub hooked: Option<u32>,
+}
+
+impl SimPlayer {
+    pub fn dead(&self) -> bool {
+        self.death.is_some()
+    }
+
+    /// The dead drop their rope, the first cause of death is the one that counts
+    fn kill(&mut self, cause: DeathCause) {
+        self.death.get_or_insert(cause);
+        self.hooked = None;
+    }
+}
+
+/// The game without any rendering, ticking exactly like the fixed timestep
+/// systems do
+#[derive(Clone)]
+pub struct Simulation {
+    pub physics: Physics,
+    /// Indexed by player id
+    pub players: Vec<SimPlayer>,
+    pub mines: Vec<SimMine>,
+    pub hazards: Vec<Hazard>,
+    pub pickups: Vec<Pickup>,
+    /// Ticks since the start
+    pub tick: u32,
+    // endless runs keep spawning mines, levels bring their own
+    layout: Option<MineLayout>,
+    // highest players left out of a snapshot got, the screen follows them too
+    outside_top: f32,
+}
+
+impl Simulation {
+    /// An endless run on the layout of `seed`, with one player
+    pub fn endless(seed: u64, physics: Physics) -> Self {
+        Self::new(physics, Some(MineLayout::new(seed)))
+    }
+
+    pub fn level(level: &Level, physics: Physics) -> Self {
+        let mut sim = Self::new(physics, None);
+        sim.mines = level
+            .mines
+            .iter()
+            .enumerate()
+            .map(|(i, m)| SimMine {
+                id: i as u32,
+                position: Vec3::new(m.x, m.y, 1.0),
+                velocity: Vec3::ZERO,
+                kind: m.kind,
+            })
+            .collect();
+        sim.hazards = level.hazards.clone();
+        sim.pickups = level.pickups.clone();
+        sim
+    }
+
+    fn new(physics: Physics, layout: Option<MineLayout>) -> Self {
+        let mut sim = Self {
+            physics,
+            players: Vec::new(),
+            mines: Vec::new(),
+            hazards: Vec::new(),
+            pickups: Vec::new(),
+            tick: 0,
+            layout,
+            outside_top: f32::MIN,
+        };
+        sim.add_player(0.0);
+        sim
+    }
+
+    /// A world with no more mines coming than the ones given, for trying things
+    /// out, `top` is the highest anyone got, including players left out
+    pub fn snapshot(
+        physics: Physics,
+        players: Vec<SimPlayer>,
+        mines: Vec<SimMine>,
+        top: f32,
+    ) -> Self {
+        Self {
+            physics,
+            players,
+            mines,
+            hazards: Vec::new(),
+            pickups: Vec::new(),
+            tick: 0,
+            layout: None,
+            outside_top: top,
+        }
+    }
+
+    /// Another player at the start height, returns its id
+    pub fn add_player(&mut self, x: f32) -> u8 {
+        self.players.push(SimPlayer {
+            position: Vec3::new(x, START_OFFSET, 1.0),
+            velocity: start_velocity(),
+            maxheight: 0.0,
+            death: None,
+            feather: 0.0,
+            hooked: None,
+        });
+        (self.players.len() - 1) as u8
+    }
+
+    pub fn mine(&self, id: u32) -> Option<&SimMine> {
+        self.mines.iter().find(|m| m.id == id)
+    }
+
+    /// Highest any player got, the screen follows it
+    pub fn top(&self) -> f32 {
+        self.players
+            .iter()
+            .map(|p| p.maxheight)
+            .fold(self.outside_top, f32::max)
+    }
+
+    /// The run is over once everyone is dead
+    pub fn over(&self) -> bool {
+        self.players.iter().all(|p| p.dead())
+    }
+
+    /// Hook a mine, or release with None
+    pub fn hook(&mut self, player: u8, mine: Option<u32>) {
+        let mine = mine.filter(|id| self.mine(*id).is_some());
+        if let Some(p) = self.players.get_mut(player as usize) {
+            p.hooked = mine;
+        }
+    }
+
+    /// One fixed timestep, in the same order as the game runs its systems
+    pub fn step(&mut self) {
+        if self.over() {
+            return;
+        }
+        let physics = self.physics;
+
+        // forces
+        for p in self.players.iter_mut() {
+            if let Some(m) = self.mines.iter().find(|m| Some(m.id) == p.hooked) {
+                physics.pull_player(&mut p.velocity, p.position, m.position, m.kind);
+            }
+        }
+        for m in self.mines.iter_mut() {
+            let pulling: Vec<Vec3> = self
+                .players
+                .iter()
+                .filter(|p| p.hooked == Some(m.id))
+                .map(|p| p.position)
+                .collect();
+            physics.pull_mine(&mut m.velocity, m.position, &pulling, m.kind);
+        }
+        for p in self.players.iter_mut() {
+            physics.fall(&mut p.velocity, &mut p.feather);
+            physics.collide_walls(&mut p.position, &mut p.velocity);
+        }
+
+        // movement
+        for p in self.players.iter_mut() {
+            p.position += p.velocity;
+            p.maxheight = p.position.y.max(p.maxheight);
+        }
+        for m in self.mines.iter_mut() {
+            m.position += m.velocity;
+        }
+
+        // everything after the movement
+        let top = self.top();
+        for p in self.players.iter_mut() {
+            if too_low(top, p.position.y) {
+                p.kill(DeathCause::Fell);
+            }
+            for h in self.hazards.iter() {
+                let size = Vec2::new(h.width, h.height);
+                let center = Vec3::new(h.x, h.y, 0.0);
+                if physics::overlaps(p.position, Vec2::splat(PLAYER_SIZE), center, size) {
+                    p.kill(DeathCause::Hazard);
+                }
+            }
+        }
+        let players = &mut self.players;
+        self.pickups.retain(|pu| {
+            let center = Vec3::new(pu.x, pu.y, 0.0);
+            // the first player to touch a pickup gets it
+            let hit = players.iter_mut().find(|p| {
+                physics::overlaps(
+                    p.position,
+                    Vec2::splat(PLAYER_SIZE),
+                    center,
+                    Vec2::splat(PICKUP_SIZE),
+                )
+            });
+            match hit {
+                Some(p) => {
+                    physics::collect(pu.kind, &mut p.velocity, &mut p.feather);
+                    false
+                }
+                None => true,
+            }
+        });
+        if let Some(layout) = &mut self.layout {
+            if layout.needs_mine(top) {
+                let (id, pos) = layout.next(&physics);
+                self.mines.push(SimMine {
+                    id,
+                    position: pos.extend(1.0),
+                    velocity: Vec3::ZERO,
+                    kind: MineKind::Normal,
+                });
+            }
+        }
+        let bottom = top + GAME_BOARD.2;
+        self.mines.retain(|m| m.position.y >= bottom);
+        // despawning a mine also releases the rope
+        for p in self.players.iter_mut() {
+            if let Some(id) = p.hooked {
+                if !self.mines.iter().any(|m| m.id == id) {
+                    p.hooked = None;
+                }
+            }
+        }
+        self.tick += 1;
+    }
+}
+
+/// Feeds the hooks of a replay into a simulation at the ticks they happened
+#[derive(Clone)]
+pub struct Playback {
+    pub sim: Simulation,
+    events: Vec<HookEvent>,
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(sim: Simulation, replay: &Replay) -> Self {
+        Self {
+            sim,
+            events: replay.events.clone(),
+            next: 0,
+        }
+    }
+
+    pub fn step(&mut self) {
+        while let Some(event) = self.events.get(self.next) {
+            if event.tick > self.sim.tick {
+                break;
+            }
+            self.sim.hook(event.player, event.mine);
+            self.next += 1;
+        }
+        self.sim.step();
+    }
+}
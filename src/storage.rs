@@ -0,0 +1,43 @@
+// Tiny key/value store for things that outlive a run: files in the working
+// directory natively, localStorage on the web
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "save";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::path::Path::new(SAVE_DIR).join(format!("{}.json", key))
+}
+
+/// Read what was last saved under `key`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)).ok()
+}
+
+/// Overwrite whatever is saved under `key`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, value: &str) -> Result<(), String> {
+    std::fs::create_dir_all(SAVE_DIR).map_err(|e| e.to_string())?;
+    std::fs::write(path(key), value).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Read what was last saved under `key`
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// Overwrite whatever is saved under `key`
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, value: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or_else(|| "no localStorage".to_string())?
+        .set_item(key, value)
+        .map_err(|e| format!("{:?}", e))
+}
@@ -0,0 +1,150 @@
+// Style points on top of the height: hooks in a row without touching a wall
+// build a multiplier, long swings and close calls with the bottom of the
+// screen earn bonuses
+
+use bevy::prelude::*;
+
+use crate::physics::{GAME_BOARD, TIME_STEP};
+use crate::sim::GameEvent;
+use crate::{top_height, Player, PlayerId, RunTimer};
+
+const HOOK_POINTS: f32 = 10.;
+// every hook in a row after the first adds this much to the multiplier
+const COMBO_STEP: f32 = 0.25;
+const MAX_MULTIPLIER: f32 = 4.;
+// swings held longer than this many seconds pay for every second beyond it
+const LONG_SWING: f32 = 1.5;
+const SWING_POINTS: f32 = 20.;
+// closer than this to the bottom of the screen is a close call
+const NEAR_MISS: f32 = 40.;
+// which only pays once the player got this far away from it again
+const NEAR_MISS_ESCAPE: f32 = 120.;
+const NEAR_MISS_POINTS: f32 = 50.;
+
+/// The style side of the first player's run, kept for the game over screen
+#[derive(Debug, Default, Clone)]
+pub(crate) struct StyleScore {
+    pub(crate) height: f32,
+    /// Hooks in a row without touching a wall
+    pub(crate) combo: u32,
+    pub(crate) best_combo: u32,
+    pub(crate) hooks: u32,
+    pub(crate) hook_points: f32,
+    pub(crate) long_swings: u32,
+    pub(crate) swing_points: f32,
+    pub(crate) near_misses: u32,
+    pub(crate) near_miss_points: f32,
+    // tick the current swing started at
+    swing_start: Option<u32>,
+    // grabbing the same mine again doesn't count as another hook
+    last_mine: Option<u32>,
+    // got close to the bottom and hasn't gotten away yet
+    in_danger: bool,
+}
+
+impl StyleScore {
+    pub(crate) fn multiplier(&self) -> f32 {
+        (1. + COMBO_STEP * self.combo.saturating_sub(1) as f32).min(MAX_MULTIPLIER)
+    }
+
+    pub(crate) fn style(&self) -> f32 {
+        self.hook_points + self.swing_points + self.near_miss_points
+    }
+
+    /// Height and style together
+    pub(crate) fn total(&self) -> f32 {
+        self.height + self.style()
+    }
+
+    fn hooked(&mut self, tick: u32, mine: u32) {
+        // hooking the next mine ends the swing on the last one
+        self.end_swing(tick);
+        self.swing_start = Some(tick);
+        if self.last_mine == Some(mine) {
+            return;
+        }
+        self.last_mine = Some(mine);
+        self.combo += 1;
+        self.best_combo = self.best_combo.max(self.combo);
+        self.hooks += 1;
+        self.hook_points += HOOK_POINTS * self.multiplier();
+    }
+
+    fn end_swing(&mut self, tick: u32) {
+        if let Some(start) = self.swing_start.take() {
+            let seconds = tick.saturating_sub(start) as f32 * TIME_STEP;
+            if seconds > LONG_SWING {
+                self.long_swings += 1;
+                self.swing_points += (seconds - LONG_SWING) * SWING_POINTS * self.multiplier();
+            }
+        }
+    }
+
+    /// Nothing pays for the swing or the close call that killed
+    fn died(&mut self) {
+        self.combo = 0;
+        self.swing_start = None;
+        self.in_danger = false;
+    }
+
+    /// `above_bottom` is how far the player is above the bottom of the screen
+    fn track_near_miss(&mut self, above_bottom: f32) {
+        if above_bottom < NEAR_MISS {
+            self.in_danger = true;
+        } else if self.in_danger && above_bottom > NEAR_MISS_ESCAPE {
+            self.in_danger = false;
+            self.near_misses += 1;
+            self.near_miss_points += NEAR_MISS_POINTS * self.multiplier();
+        }
+    }
+
+    /// Lines for the game over screen
+    pub(crate) fn breakdown(&self) -> Vec<String> {
+        vec![
+            format!("Height: {}", self.height as i32),
+            format!("Hooks: {} (+{})", self.hooks, self.hook_points as i32),
+            format!(
+                "Long swings: {} (+{})",
+                self.long_swings, self.swing_points as i32
+            ),
+            format!(
+                "Near misses: {} (+{})",
+                self.near_misses, self.near_miss_points as i32
+            ),
+            format!("Best combo: x{}", self.best_combo),
+            format!("Score: {}", self.total() as i32),
+        ]
+    }
+}
+
+pub(crate) fn reset_style(mut score: ResMut<StyleScore>) {
+    *score = StyleScore::default();
+}
+
+/// Score the first player's style once per tick, after everything moved
+pub(crate) fn style_system(
+    timer: Res<RunTimer>,
+    mut score: ResMut<StyleScore>,
+    mut events: EventReader<GameEvent>,
+    q_player: Query<(&Player, &PlayerId, &Transform)>,
+) {
+    for event in events.iter() {
+        match event {
+            GameEvent::Hooked {
+                player: 0, mine, ..
+            } => score.hooked(timer.ticks, *mine),
+            GameEvent::Released { player: 0 } => score.end_swing(timer.ticks),
+            GameEvent::WallBounce { player: 0, .. } => score.combo = 0,
+            GameEvent::Died { player: 0, .. } => score.died(),
+            _ => {}
+        }
+    }
+    let top = top_height(q_player.iter().map(|(p, _, _)| p.maxheight)).unwrap_or(0.);
+    let first = q_player.iter().find(|(_, id, _)| **id == PlayerId(0));
+    if let Some((player, _, p_t)) = first {
+        score.height = player.maxheight;
+        if !player.dead() {
+            score.track_near_miss(p_t.translation.y - (top + GAME_BOARD.2));
+        }
+    }
+}
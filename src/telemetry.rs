@@ -0,0 +1,73 @@
+// Every GameEvent as a line of JSON, only natively and only when the
+// UPWARDS_TELEMETRY environment variable names the file to append to
+
+use std::io::Write;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::sim::GameEvent;
+use crate::RunTimer;
+
+#[cfg(not(target_arch = "wasm32"))]
+const TELEMETRY_VAR: &str = "UPWARDS_TELEMETRY";
+
+/// Where the events go, nowhere unless telemetry was asked for
+pub struct Telemetry(Option<Box<dyn Write + Send + Sync>>);
+
+impl Default for Telemetry {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default() -> Self {
+        let path = match std::env::var(TELEMETRY_VAR) {
+            Ok(path) => path,
+            Err(_) => return Telemetry(None),
+        };
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path);
+        match file {
+            Ok(file) => Telemetry(Some(Box::new(std::io::BufWriter::new(file)))),
+            Err(e) => {
+                warn!("can't write telemetry to {}: {}", path, e);
+                Telemetry(None)
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn default() -> Self {
+        Telemetry(None)
+    }
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    tick: u32,
+    #[serde(flatten)]
+    event: &'a GameEvent,
+}
+
+/// Write the events of this tick, stamped with it
+pub fn telemetry_system(
+    mut telemetry: ResMut<Telemetry>,
+    timer: Res<RunTimer>,
+    mut events: EventReader<GameEvent>,
+) {
+    let out = match &mut telemetry.0 {
+        Some(out) => out,
+        None => return,
+    };
+    for event in events.iter() {
+        let line = Line {
+            tick: timer.ticks,
+            event,
+        };
+        let json = serde_json::to_string(&line).expect("Events are always valid JSON");
+        if let Err(e) = writeln!(out, "{}", json).and_then(|_| out.flush()) {
+            warn!("telemetry stopped: {}", e);
+            telemetry.0 = None;
+            return;
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::cmp::Ordering;
+
+use bevy::math::{Vec2, Vec3};
+use rand::Rng;
+
+use crate::layout::MINE_GAP;
+use crate::physics::{on_screen, too_low, MineKind, Physics, GAME_BOARD};
+
+// a hook or release is held for this many ticks before the next decision
+const DECISION_TICKS: u32 = 5;
+// give up after 10 seconds of game time
+const MAX_DECISIONS: u32 = 60;
+// how many of the most promising states are followed per decision
+const BEAM_WIDTH: usize = 24;
+// how often a bad placement is rolled again before it gets repaired
+const MAX_REROLLS: u32 = 4;
+// how often a bad placement is moved towards the highest mine
+const MAX_REPAIRS: u32 = 4;
+// the player starts hanging this far below the highest mine
+const START_BELOW_MINE: f32 = 32.;
+// the next mine counts as reached once the player gets this close to it
+pub const HOOK_REACH: f32 = 200.;
+
+enum Outcome {
+    Reached,
+    Alive,
+    Dead,
+}
+
+/// A copy of the world the validator can play with
+#[derive(Clone)]
+struct Probe {
+    position: Vec3,
+    velocity: Vec3,
+    maxheight: f32,
+    // position, velocity
+    mines: Vec<(Vec3, Vec3)>,
+    hooked: Option<usize>,
+    // the mine being placed, it can't be hooked yet
+    next: Vec3,
+    // closest the player got to `next` so far
+    closest: f32,
+}
+
+impl Probe {
+    /// One fixed timestep of the game
+    fn tick(&mut self, physics: &Physics) {
+        if let Some(i) = self.hooked {
+            physics.pull_player(
+                &mut self.velocity,
+                self.position,
+                self.mines[i].0,
+                MineKind::Normal,
+            );
+        }
+        let player = [self.position];
+        for (i, (m_pos, m_vel)) in self.mines.iter_mut().enumerate() {
+            let pulling: &[Vec3] = if self.hooked == Some(i) { &player } else { &[] };
+            physics.pull_mine(m_vel, *m_pos, pulling, MineKind::Normal);
+        }
+        physics.gravity(&mut self.velocity);
+        physics.collide_walls(&mut self.position, &mut self.velocity);
+
+        self.position += self.velocity;
+        for (m_pos, m_vel) in self.mines.iter_mut() {
+            *m_pos += *m_vel;
+        }
+        self.maxheight = self.position.y.max(self.maxheight);
+        self.closest = self.closest.min(self.distance());
+
+        // the game despawns mines below the screen, which also releases the rope
+        if let Some(i) = self.hooked {
+            if self.mines[i].0.y < self.maxheight + GAME_BOARD.2 {
+                self.hooked = None;
+            }
+        }
+    }
+
+    fn distance(&self) -> f32 {
+        (self.next - self.position).truncate().length()
+    }
+
+    fn run(&mut self, physics: &Physics, ticks: u32) -> Outcome {
+        for _ in 0..ticks {
+            self.tick(physics);
+            if self.closest <= HOOK_REACH {
+                return Outcome::Reached;
+            }
+            if too_low(self.maxheight, self.position.y) {
+                return Outcome::Dead;
+            }
+        }
+        Outcome::Alive
+    }
+
+    /// Highest point the player gets to if it just flies on
+    fn apex(&self, physics: &Physics) -> f32 {
+        let mut apex = self.maxheight;
+        if self.velocity.y > 0. && physics.gravity > 0. {
+            apex = apex.max(self.position.y + self.velocity.y.powi(2) / (2. * physics.gravity));
+        }
+        apex
+    }
+
+    /// Lower is more promising: how far the next mine is sideways, and how far
+    /// above where the player is flying to
+    fn cost(&self, physics: &Physics) -> f32 {
+        (self.next.x - self.position.x).abs() + (self.next.y - self.apex(physics)).max(0.)
+    }
+
+    /// Release the rope, or grab any mine on screen
+    fn choices(&self) -> Vec<Option<usize>> {
+        let mut choices = vec![None];
+        for (i, (m_pos, _)) in self.mines.iter().enumerate() {
+            if on_screen(self.maxheight, m_pos.y) {
+                choices.push(Some(i));
+            }
+        }
+        choices
+    }
+}
+
+fn highest(mines: &[Vec2]) -> Option<Vec2> {
+    mines
+        .iter()
+        .copied()
+        .max_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+}
+
+/// Check if the player can get within HOOK_REACH of `next` when it is
+/// hanging still below the highest of `mines`, bouncing off the walls on the
+/// way, by searching through hook / release sequences on the existing mines
+pub fn next_mine_reachable(mines: &[Vec2], next: Vec2, physics: &Physics) -> bool {
+    closest_approach(mines, next, physics) <= HOOK_REACH
+}
+
+/// How close the search got the player to `next`, it stops once that is
+/// within HOOK_REACH
+fn closest_approach(mines: &[Vec2], next: Vec2, physics: &Physics) -> f32 {
+    let top = match highest(mines) {
+        Some(top) => top,
+        // nothing to grab yet, the player starts with enough speed
+        None => return 0.,
+    };
+    let start = top.extend(1.0) - Vec3::Y * START_BELOW_MINE;
+    let next = next.extend(1.0);
+    let mut beam = vec![Probe {
+        position: start,
+        velocity: Vec3::ZERO,
+        maxheight: start.y,
+        mines: mines.iter().map(|m| (m.extend(1.0), Vec3::ZERO)).collect(),
+        hooked: None,
+        next,
+        closest: (next - start).truncate().length(),
+    }];
+    let mut closest = beam[0].closest;
+
+    for _ in 0..MAX_DECISIONS {
+        let mut children = Vec::new();
+        for probe in beam.iter() {
+            for choice in probe.choices() {
+                let mut child = probe.clone();
+                child.hooked = choice;
+                let outcome = child.run(physics, DECISION_TICKS);
+                closest = closest.min(child.closest);
+                match outcome {
+                    Outcome::Reached => return closest,
+                    Outcome::Alive => children.push(child),
+                    Outcome::Dead => {}
+                }
+            }
+        }
+        if children.is_empty() {
+            break;
+        }
+        children.sort_by(|a, b| {
+            a.cost(physics)
+                .partial_cmp(&b.cost(physics))
+                .unwrap_or(Ordering::Equal)
+        });
+        children.truncate(BEAM_WIDTH);
+        beam = children;
+    }
+    closest
+}
+
+/// Random spot between the walls, in the 50 units above `bottom`
+pub fn random_mine_position<R: Rng>(rng: &mut R, bottom: f32, physics: &Physics) -> Vec2 {
+    Vec2::new(
+        rng.gen_range(-physics.half_width..physics.half_width),
+        rng.gen_range(bottom..bottom + 50.),
+    )
+}
+
+/// Pick a spot above `bottom` for the next mine, rolling again a few times if
+/// the validator says it can't be reached and repairing the last roll otherwise,
+/// None if even the repair failed
+pub fn place_mine<R: Rng>(
+    rng: &mut R,
+    bottom: f32,
+    mines: &[Vec2],
+    physics: &Physics,
+) -> Option<Vec2> {
+    let mut candidate = random_mine_position(rng, bottom, physics);
+    for _ in 0..MAX_REROLLS {
+        if next_mine_reachable(mines, candidate, physics) {
+            return Some(candidate);
+        }
+        candidate = random_mine_position(rng, bottom, physics);
+    }
+    repair(candidate, bottom, mines, physics)
+}
+
+/// Move a bad placement halfway towards the highest mine until it can be
+/// reached, but never below `bottom` or closer than MINE_GAP above that mine,
+/// None if it still can't be reached after MAX_REPAIRS moves
+pub fn repair(mut candidate: Vec2, bottom: f32, mines: &[Vec2], physics: &Physics) -> Option<Vec2> {
+    for _ in 0..MAX_REPAIRS {
+        if next_mine_reachable(mines, candidate, physics) {
+            return Some(candidate);
+        }
+        // without mines everything is reachable, so there is a highest one here
+        let top = highest(mines)?;
+        candidate = (candidate + top) / 2.;
+        candidate.y = candidate.y.max(bottom).max(top.y + MINE_GAP);
+    }
+    Some(candidate).filter(|c| next_mine_reachable(mines, *c, physics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two mines the player can swing between
+    fn mines() -> [Vec2; 2] {
+        [Vec2::new(-100., 0.), Vec2::new(100., 200.)]
+    }
+
+    fn start() -> Vec2 {
+        mines()[1] - Vec2::Y * START_BELOW_MINE
+    }
+
+    #[test]
+    fn reachable_with_a_swing() {
+        // straight above, but too far to get to without swinging up first
+        let next = Vec2::new(100., 500.);
+        assert!(next.distance(start()) > HOOK_REACH);
+        assert!(next_mine_reachable(&mines(), next, &Physics::default()));
+    }
+
+    #[test]
+    fn unreachable_across_the_board() {
+        // at the far wall, no swing gets there
+        let next = Vec2::new(-200., 450.);
+        assert!(!next_mine_reachable(&mines(), next, &Physics::default()));
+    }
+
+    #[test]
+    fn repair_stays_above_bottom() {
+        let physics = Physics::default();
+        let bottom = mines()[1].y + MINE_GAP;
+        let pos = repair(Vec2::new(-200., bottom), bottom, &mines(), &physics).unwrap();
+        assert!(pos.y >= bottom);
+        assert!(pos.x > -200.);
+        assert!(next_mine_reachable(&mines(), pos, &physics));
+    }
+}